@@ -1,141 +1,588 @@
-fn main() -> Result<(), &'static str> {
+fn main() -> Result<(), String> {
+    // Only rerun when the inputs that can change the embedded data change, not whenever any file
+    // in the package does.
+    println!("cargo:rerun-if-changed=build.rs");
+    for var in &[
+        "DB_IP_LITE_MONTH",
+        "DB_IP_LITE_SHA256",
+        "DB_IP_CACHE_DIR",
+        "XDG_CACHE_HOME",
+        "HOME",
+        "LOCALAPPDATA",
+        "SOURCE_DATE_EPOCH",
+        "DB_IP_LITE_FAMILY",
+        "DB_IP_LITE_COUNTRIES",
+        "DB_IP_LITE_OTHER",
+    ] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
     #[cfg(feature = "download-country-lite")]
     {
-        fn download_region_lite(output_path: &str) -> Result<(), &'static str> {
-            use chrono::{Datelike, Duration, TimeZone, Utc};
+        use std::env;
+        use std::fs;
+        use std::path::{Path, PathBuf};
+
+        /// A downloaded lite database.
+        struct Lite {
+            year: i32,
+            month: u32,
+            url: String,
+            sha256: String,
+            csv_path: PathBuf,
+        }
+
+        fn download_region_lite(cache_dir: &Path) -> Result<Lite, String> {
+            use db_ip_core::lite::{lite_months, lite_url, parse_month, LITE_BASE_URL};
             use flate2::bufread::GzDecoder;
-            use std::fs;
-            use std::fs::File;
-            use std::io;
-            use std::io::BufReader;
-            use std::ops::Sub;
-            use std::time::SystemTime;
-
-            for i in 0..48 {
-                let date = Utc::now().date().sub(
-                    Duration::from_std(std::time::Duration::from_secs(i * 31 * 24 * 3600)).unwrap(),
-                );
+            use sha2::{Digest, Sha256};
+            use std::io::Read;
 
-                if download_lite(date.year(), date.month(), output_path).is_ok() {
-                    return Ok(());
-                }
-            }
+            // Pinning the month, and optionally the checksum, makes the build reproducible.
+            let months: Box<dyn Iterator<Item = (i32, u32)>> = match env::var("DB_IP_LITE_MONTH") {
+                Ok(month) => Box::new(std::iter::once(parse_month(&month).ok_or_else(|| {
+                    format!("DB_IP_LITE_MONTH={:?} is not formatted as YYYY-MM", month)
+                })?)),
+                Err(_) => Box::new(lite_months(now(None))),
+            };
+            let expected_sha256 = env::var("DB_IP_LITE_SHA256")
+                .ok()
+                .map(|sha256| sha256.trim().to_ascii_lowercase());
 
-            if download_lite(2022, 2, output_path).is_ok() {
-                return Ok(());
+            for (year, month) in months {
+                if let Some(lite) =
+                    download_lite(year, month, cache_dir, expected_sha256.as_deref())?
+                {
+                    return Ok(lite);
+                }
             }
 
-            return Err("db_ip could not download country lite database");
+            return Err("db_ip could not download country lite database".to_owned());
 
+            /// Returns [`None`] if the month wasn't available, or an error that must fail the
+            /// build.
             fn download_lite(
                 year: i32,
                 month: u32,
-                output_path: &str,
-            ) -> Result<bool, &'static str> {
-                let url = format!(
-                    "https://download.db-ip.com/free/dbip-country-lite-{}-{:02}.csv.gz",
-                    year, month
-                );
-                let expiry = Utc.ymd(year, month, 1).and_hms(0, 0, 0);
-                let res = download_file(&url, output_path, Some(SystemTime::from(expiry)));
-                match res {
-                    Ok(downloaded) => {
+                cache_dir: &Path,
+                expected_sha256: Option<&str>,
+            ) -> Result<Option<Lite>, String> {
+                let url = lite_url(LITE_BASE_URL, "country", (year, month));
+                let csv_path =
+                    cache_dir.join(format!("dbip-country-lite-{}-{:02}.csv", year, month));
+                match download_file(&url, &csv_path, expected_sha256) {
+                    Ok((sha256, downloaded)) => {
                         if downloaded {
-                            println!("cargo:warning=db_ip downloaded {} (please read the db-ip.com license terms!)", url);
-                        } else {
-                            println!(
-                                "cargo:warning=db_ip skipped download, already up to date with {}",
-                                url
-                            );
+                            println!("cargo:warning=db_ip downloaded {} with SHA-256 {} (please read the db-ip.com license terms!)", url, sha256);
                         }
+                        Ok(Some(Lite {
+                            year,
+                            month,
+                            url,
+                            sha256,
+                            csv_path,
+                        }))
                     }
-                    Err(e) => println!(
-                        "cargo:warning=db_ip error downloading {} database: {:?}",
-                        expiry, e
-                    ),
+                    Err(DownloadError::Unavailable(e)) => {
+                        println!(
+                            "cargo:warning=db_ip error downloading {}-{:02} database: {:?}",
+                            year, month, e
+                        );
+                        Ok(None)
+                    }
+                    Err(DownloadError::Fatal(e)) => Err(e),
                 }
-                res
             }
 
+            enum DownloadError {
+                /// Another month may be tried.
+                Unavailable(&'static str),
+                /// The build must fail.
+                Fatal(String),
+            }
+
+            /// Returns the SHA-256 of the downloaded file, and whether it was downloaded (as
+            /// opposed to previously downloaded from `url` to `path`).
             fn download_file(
                 url: &str,
-                path: &str,
-                expiry: Option<SystemTime>,
-            ) -> Result<bool, &'static str> {
-                if expiry
-                    .and_then(|e| fs::metadata(path).ok().map(|md| (e, md)))
-                    .and_then(|(e, md)| md.modified().ok().map(|st| (e, st)))
-                    .map(|(e, st)| st <= e)
-                    .unwrap_or(true)
+                path: &Path,
+                expected_sha256: Option<&str>,
+            ) -> Result<(String, bool), DownloadError> {
+                use DownloadError::*;
+
+                // Records which file was decompressed to `path`, as `{url} {sha256}`.
+                let source_path = path.with_extension("csv.source");
+                if let Some((source_url, sha256)) = fs::read_to_string(&source_path)
+                    .ok()
+                    .as_deref()
+                    .and_then(|source| source.trim().split_once(' '))
                 {
-                    let mut resp = reqwest::blocking::get(url).map_err(|_| "request failed")?;
-                    if !resp.status().is_success() {
-                        return Err("failed to download");
+                    if source_url == url
+                        && (expected_sha256.is_none() || expected_sha256 == Some(sha256))
+                        && path.is_file()
+                    {
+                        return Ok((sha256.to_owned(), false));
                     }
-                    let mut out = File::create(path).map_err(|_| "failed to create file")?;
-                    let mut decoded = GzDecoder::new(BufReader::new(&mut resp));
-                    io::copy(&mut decoded, &mut out)
-                        .map(|_| true)
-                        .map_err(|_| "failed to copy content")
-                } else {
-                    Ok(false)
                 }
+
+                let mut resp =
+                    reqwest::blocking::get(url).map_err(|_| Unavailable("request failed"))?;
+                if !resp.status().is_success() {
+                    return Err(Unavailable("failed to download"));
+                }
+                let mut gz = Vec::new();
+                resp.read_to_end(&mut gz)
+                    .map_err(|_| Unavailable("failed to read content"))?;
+
+                let sha256 = Sha256::digest(&gz)
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                if let Some(expected) = expected_sha256 {
+                    if expected != sha256 {
+                        return Err(Fatal(format!(
+                            "db_ip downloaded {} with SHA-256 {}, but DB_IP_LITE_SHA256 is {}",
+                            url, sha256, expected
+                        )));
+                    }
+                }
+
+                write_atomic(path, |out| {
+                    std::io::copy(&mut GzDecoder::new(gz.as_slice()), out).map(|_| ())
+                })
+                .map_err(|_| Unavailable("failed to decompress"))?;
+                write_atomic(&source_path, |out| {
+                    std::io::Write::write_all(out, format!("{} {}", url, sha256).as_bytes())
+                })
+                .map_err(|_| Unavailable("failed to write source"))?;
+                Ok((sha256, true))
             }
         }
 
-        use std::env;
-        let csv_path = format!("{}/country_lite.csv", env::var("OUT_DIR").unwrap());
-        if download_region_lite(&csv_path).is_ok() {
+        /// How [`write_static`] encodes values, to be decoded at compile time.
+        #[cfg(feature = "static-lite")]
+        trait StaticValue: db_ip_core::IpData + serde_1::de::DeserializeOwned {
+            const NAME: &'static str;
+            /// Appends the encoding of `value`.
+            fn encode(value: Option<Self>, bytes: &mut Vec<u8>);
+        }
+
+        /// See `CountryCode::static_values`.
+        #[cfg(all(feature = "static-lite", feature = "include-country-code-lite"))]
+        impl StaticValue for db_ip_core::CountryCode {
+            const NAME: &'static str = "db_ip_core::CountryCode";
+
+            fn encode(value: Option<Self>, bytes: &mut Vec<u8>) {
+                bytes
+                    .extend_from_slice(value.as_ref().map_or(b"\0\0", |cc| cc.as_str().as_bytes()));
+            }
+        }
+
+        /// See `Region::static_values`.
+        #[cfg(all(feature = "static-lite", feature = "include-region-lite"))]
+        impl StaticValue for db_ip_core::Region {
+            const NAME: &'static str = "db_ip_core::Region";
+
+            fn encode(value: Option<Self>, bytes: &mut Vec<u8>) {
+                use db_ip_core::Region::*;
+
+                bytes.push(match value {
+                    None => 0,
+                    Some(Africa) => 1,
+                    Some(Asia) => 2,
+                    Some(Europe) => 3,
+                    Some(NorthAmerica) => 4,
+                    Some(Oceania) => 5,
+                    Some(SouthAmerica) => 6,
+                });
+            }
+        }
+
+        /// Which of the downloaded data to embed, configured by `DB_IP_LITE_FAMILY` (`ipv4` or
+        /// `ipv6`), `DB_IP_LITE_COUNTRIES` (comma separated country codes) and `DB_IP_LITE_OTHER`
+        /// (the country code that replaces all others, or none if unset).
+        #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
+        struct Restriction {
+            family: Option<db_ip_core::AddressFamily>,
+            countries: Option<Vec<db_ip_core::CountryCode>>,
+            other: Option<db_ip_core::CountryCode>,
+        }
+
+        #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
+        impl Restriction {
+            fn from_env() -> Result<Self, String> {
+                use db_ip_core::{AddressFamily, CountryCode};
+
+                let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+                let country_code = |cc: &str| {
+                    CountryCode::from_str(cc.trim())
+                        .ok_or_else(|| format!("{:?} is not a two letter country code", cc))
+                };
+                let family = match var("DB_IP_LITE_FAMILY").as_deref() {
+                    None => None,
+                    Some("ipv4") => Some(AddressFamily::Ipv4),
+                    Some("ipv6") => Some(AddressFamily::Ipv6),
+                    Some(family) => {
+                        return Err(format!(
+                            "DB_IP_LITE_FAMILY={:?} is neither ipv4 nor ipv6",
+                            family
+                        ))
+                    }
+                };
+                let countries = var("DB_IP_LITE_COUNTRIES")
+                    .map(|countries| {
+                        let mut countries = countries
+                            .split(',')
+                            .map(country_code)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        countries.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                        countries.dedup();
+                        Ok::<_, String>(countries)
+                    })
+                    .transpose()?;
+                let other = var("DB_IP_LITE_OTHER")
+                    .map(|other| country_code(&other))
+                    .transpose()?;
+                if other.is_some() && countries.is_none() {
+                    return Err("DB_IP_LITE_OTHER requires DB_IP_LITE_COUNTRIES".to_owned());
+                }
+                Ok(Self {
+                    family,
+                    countries,
+                    other,
+                })
+            }
+
+            /// Distinguishes conversions with different restrictions in the cache.
+            fn cache_suffix(&self) -> String {
+                use sha2::{Digest, Sha256};
+
+                if self.family.is_none() && self.countries.is_none() {
+                    return String::new();
+                }
+                let description = format!(
+                    "{:?} {:?} {:?}",
+                    self.family,
+                    self.countries
+                        .iter()
+                        .flatten()
+                        .map(|cc| cc.as_str())
+                        .collect::<Vec<_>>(),
+                    self.other.as_ref().map(|cc| cc.as_str())
+                );
+                let hash = Sha256::digest(description.as_bytes());
+                let hex = hash[..8]
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                format!("-restricted-{}", hex)
+            }
+
+            fn apply(
+                &self,
+                mut db: db_ip_core::DbIpDatabase<db_ip_core::CountryCode>,
+            ) -> db_ip_core::DbIpDatabase<db_ip_core::CountryCode> {
+                if let Some(family) = self.family {
+                    db = db.restrict_family(family);
+                }
+                if let Some(countries) = &self.countries {
+                    db = db.restrict(|cc| countries.contains(cc), self.other);
+                }
+                db
+            }
+        }
+
+        /// Writes a file such that concurrent builds sharing a cache never see it partially
+        /// written.
+        fn write_atomic(
+            path: &Path,
+            write: impl FnOnce(&mut fs::File) -> std::io::Result<()>,
+        ) -> std::io::Result<()> {
+            let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+            let result = fs::File::create(&tmp_path)
+                .and_then(|mut file| write(&mut file))
+                .and_then(|_| fs::rename(&tmp_path, path));
+            if result.is_err() {
+                let _ = fs::remove_file(&tmp_path);
+            }
+            result
+        }
+
+        /// Where downloaded and converted files are kept between builds: `DB_IP_CACHE_DIR`, or a
+        /// `db_ip` directory in the user's cache directory. If `DB_IP_CACHE_DIR` is empty, or no
+        /// cache directory can be created, `OUT_DIR` is used instead.
+        fn cache_dir() -> PathBuf {
+            let non_empty = |var: &str| env::var_os(var).filter(|value| !value.is_empty());
+            let dir = match env::var_os("DB_IP_CACHE_DIR") {
+                Some(dir) => Some(PathBuf::from(dir)).filter(|dir| !dir.as_os_str().is_empty()),
+                None => non_empty("XDG_CACHE_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| non_empty("HOME").map(|home| Path::new(&home).join(".cache")))
+                    .or_else(|| non_empty("LOCALAPPDATA").map(PathBuf::from))
+                    .map(|cache| cache.join("db_ip")),
+            };
+            dir.filter(|dir| fs::create_dir_all(dir).is_ok())
+                .unwrap_or_else(|| PathBuf::from(env::var("OUT_DIR").unwrap()))
+        }
+
+        /// The current time, unless overridden by `SOURCE_DATE_EPOCH`, or by the start of the
+        /// `pinned` month, for a reproducible build.
+        fn now(pinned: Option<(i32, u32)>) -> std::time::SystemTime {
+            db_ip_core::lite::build_time(
+                env::var("SOURCE_DATE_EPOCH").ok().as_deref(),
+                pinned,
+                std::time::SystemTime::now(),
+            )
+        }
+
+        /// Whether the data is pinned, in which case the build must be reproducible.
+        fn pinned() -> bool {
+            env::var_os("DB_IP_LITE_MONTH").is_some() || env::var_os("DB_IP_LITE_SHA256").is_some()
+        }
+
+        let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
+        let restriction = Restriction::from_env()?;
+        let cache_dir = cache_dir();
+        let downloaded = download_region_lite(&cache_dir);
+        if let Err(e) = &downloaded {
+            // Without pinning, a failed download is reported when the data can't be embedded.
+            if pinned() {
+                return Err(e.clone());
+            }
+        }
+        if let Ok(lite) = downloaded {
+            // Describes the embedded data, see `db_ip::LITE_METADATA`.
+            let built_at = now(Some((lite.year, lite.month)).filter(|_| pinned()))
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let metadata = format!(
+                "db_ip_core::DatabaseMetadata::lite({:?}, {}, {}, {})",
+                lite.url, lite.year, lite.month, built_at
+            );
+            if let Err(e) = fs::write(out_dir.join("lite_metadata.rs"), metadata) {
+                println!("cargo:warning=db_ip error writing metadata: {:?}", e);
+            }
+
+            /// Converts the CSV, unless it was previously converted by a build with the same
+            /// version, address families and restriction, and copies the result to `OUT_DIR`.
+            /// Values are derived with `convert` from `country_codes`, which loads the CSV.
             #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
-            fn compress_lite<V: db_ip_core::IpData + serde_1::Serialize>(
-                csv_path: &str,
-                region_path: &str,
+            fn compress_lite<
+                V: db_ip_core::IpData + serde_1::Serialize + serde_1::de::DeserializeOwned,
+            >(
+                lite: &Lite,
+                cache_dir: &Path,
+                out_path: &Path,
+                kind: &str,
+                restriction: &Restriction,
+                country_codes: &mut dyn FnMut() -> Result<
+                    db_ip_core::DbIpDatabase<db_ip_core::CountryCode>,
+                    String,
+                >,
+                convert: fn(db_ip_core::CountryCode) -> Option<V>,
             ) -> Result<(), String> {
-                use std::fs::OpenOptions;
-                use std::io::Write;
-
-                match db_ip_core::DbIpDatabase::<V>::from_csv_file(csv_path) {
-                    Err(e) => Err(format!("error: {:?}", e)),
-                    Ok(db_ip) => {
-                        let ser = bincode::serialize(&db_ip).unwrap();
-
-                        match OpenOptions::new()
-                            .create(true)
-                            .write(true)
-                            .open(region_path)
-                        {
-                            Err(e) => {
-                                Err(format!("could not open output file for writing: {:?}", e))
-                            }
-                            Ok(mut f) => {
-                                if let Err(e) = f.write_all(&ser) {
-                                    Err(format!("error writing to output file: {:?}", e))
-                                } else {
-                                    Ok(())
-                                }
-                            }
-                        }
+                let bin_path = cache_dir.join(format!(
+                    "dbip-country-lite-{}-{:02}-{}.{}-v{}{}{}{}{}.db_ip.bin",
+                    lite.year,
+                    lite.month,
+                    &lite.sha256[..lite.sha256.len().min(16)],
+                    kind,
+                    env!("CARGO_PKG_VERSION"),
+                    if cfg!(feature = "ipv4") { "-ipv4" } else { "" },
+                    if cfg!(feature = "ipv6") { "-ipv6" } else { "" },
+                    if cfg!(feature = "compressed-lite") {
+                        "-compressed"
+                    } else {
+                        ""
+                    },
+                    restriction.cache_suffix(),
+                ));
+
+                // Converts again if a previous conversion is missing, or was interrupted.
+                let cached = fs::read(&bin_path)
+                    .ok()
+                    .filter(|bytes| db_ip_core::DbIpDatabase::<V>::from_bytes(bytes).is_ok());
+                if cached.is_none() {
+                    let db_ip = country_codes()?.map(convert);
+                    #[cfg(feature = "compressed-lite")]
+                    let ser = db_ip.to_compressed_bytes();
+                    #[cfg(not(feature = "compressed-lite"))]
+                    let ser = db_ip.to_bytes();
+                    write_atomic(&bin_path, |f| std::io::Write::write_all(f, &ser))
+                        .map_err(|e| format!("error writing to output file: {:?}", e))?;
+                }
+
+                fs::copy(&bin_path, out_path)
+                    .map(|_| ())
+                    .map_err(|e| format!("error copying to output file: {:?}", e))
+            }
+
+            /// Generates an expression of a `DbIpDatabaseRef<'static, V>` from `static` arrays, so
+            /// that no deserialization is needed at runtime.
+            #[cfg(feature = "static-lite")]
+            fn write_static<V: StaticValue>(
+                bin_path: &Path,
+                out_path: &Path,
+            ) -> Result<(), String> {
+                use std::fmt::Write;
+
+                let bin = fs::read(bin_path).map_err(|e| format!("error reading: {:?}", e))?;
+                let db = db_ip_core::DbIpDatabase::<V>::from_bytes(&bin)
+                    .map_err(|e| format!("error: {:?}", e))?;
+
+                #[cfg(feature = "ipv4")]
+                let v4 = static_ranges(
+                    db.iter_v4().map(|(range, v)| {
+                        (
+                            u32::from(*range.start()).into(),
+                            u32::from(*range.end()).into(),
+                            v,
+                        )
+                    }),
+                    u32::MAX.into(),
+                );
+                #[cfg(not(feature = "ipv4"))]
+                let v4 = Vec::<(u128, Option<V>)>::new();
+                #[cfg(feature = "ipv6")]
+                let v6 = static_ranges(
+                    db.iter_v6().map(|(range, v)| {
+                        (u128::from(*range.start()), u128::from(*range.end()), v)
+                    }),
+                    u128::MAX,
+                );
+                #[cfg(not(feature = "ipv6"))]
+                let v6 = Vec::<(u128, Option<V>)>::new();
+
+                // Large array literals take too much time and memory to compile, so the arrays
+                // are decoded from files at compile time instead.
+                let mut rs = String::from("{\n");
+                for (family, ip, width, ranges) in [("v4", "u32", 4, &v4), ("v6", "u128", 16, &v6)]
+                {
+                    let mut starts = Vec::new();
+                    let mut values = Vec::new();
+                    for &(start, value) in ranges.iter() {
+                        starts.extend_from_slice(&start.to_be_bytes()[16 - width..]);
+                        V::encode(value, &mut values);
+                    }
+                    let starts_path = out_path.with_extension(format!("{}_starts.bin", family));
+                    let values_path = out_path.with_extension(format!("{}_values.bin", family));
+                    fs::write(&starts_path, starts)
+                        .map_err(|e| format!("error writing: {:?}", e))?;
+                    fs::write(&values_path, values)
+                        .map_err(|e| format!("error writing: {:?}", e))?;
+
+                    let n = ranges.len();
+                    let upper = family.to_uppercase();
+                    write!(
+                        rs,
+                        "#[allow(long_running_const_eval)]\n\
+                         static {upper}_STARTS: [{ip}; {n}] = db_ip_core::static_starts_{family}(include_bytes!({starts_path:?}));\n\
+                         #[allow(long_running_const_eval)]\n\
+                         static {upper}_VALUES: [Option<{name}>; {n}] = {name}::static_values(include_bytes!({values_path:?}));\n",
+                        upper = upper,
+                        ip = ip,
+                        n = n,
+                        family = family,
+                        starts_path = starts_path,
+                        values_path = values_path,
+                        name = V::NAME,
+                    )
+                    .unwrap();
+                }
+                rs.push_str("db_ip_core::DbIpDatabaseRef::from_parts(&V4_STARTS, &V4_VALUES, &V6_STARTS, &V6_VALUES)\n}\n");
+                fs::write(out_path, rs).map_err(|e| format!("error writing: {:?}", e))
+            }
+
+            /// Inverse of `DbIpDatabase::iter`, inserting ranges without a value between the given
+            /// inclusive ranges, as `DbIpDatabase` stores them.
+            #[cfg(feature = "static-lite")]
+            #[allow(unused)]
+            fn static_ranges<V>(
+                ranges: impl Iterator<Item = (u128, u128, V)>,
+                max: u128,
+            ) -> Vec<(u128, Option<V>)> {
+                let mut static_ranges = Vec::new();
+                let mut next = Some(0);
+                for (start, end, value) in ranges {
+                    if let Some(next) = next.filter(|&next| next < start) {
+                        static_ranges.push((next, None));
                     }
+                    static_ranges.push((start, Some(value)));
+                    next = if end == max { None } else { Some(end + 1) };
                 }
+                if let (Some(next), Some((_, Some(_)))) = (next, static_ranges.last()) {
+                    static_ranges.push((next, None));
+                }
+                static_ranges
             }
 
+            // The CSV is parsed at most once, and only if a conversion isn't cached. Other
+            // values are derived from its country codes, after any restriction.
+            #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
+            let mut loaded = None;
+            #[cfg(any(feature = "include-region-lite", feature = "include-country-code-lite"))]
+            let mut country_codes = || {
+                if loaded.is_none() {
+                    let csv_path = lite.csv_path.to_str().ok_or("non-UTF-8 cache path")?;
+                    let db_ip = db_ip_core::DbIpDatabase::from_csv_file(csv_path)
+                        .map_err(|e| format!("error: {:?}", e))?;
+                    loaded = Some(restriction.apply(db_ip));
+                }
+                Ok(loaded.clone().unwrap())
+            };
+
             #[cfg(feature = "include-region-lite")]
             {
-                let region_path = format!("{}/region_lite.bin", env::var("OUT_DIR").unwrap());
-                if let Err(e) = compress_lite::<db_ip_core::Region>(&csv_path, &region_path) {
+                let region_path = out_dir.join("region_lite.bin");
+                if let Err(e) = compress_lite(
+                    &lite,
+                    &cache_dir,
+                    &region_path,
+                    "region",
+                    &restriction,
+                    &mut country_codes,
+                    db_ip_core::Region::from_country_code,
+                ) {
                     println!("cargo:warning=db_ip error embedding region: {:?}", e);
                 }
+                #[cfg(feature = "static-lite")]
+                if let Err(e) = write_static::<db_ip_core::Region>(
+                    &region_path,
+                    &out_dir.join("region_lite.rs"),
+                ) {
+                    println!(
+                        "cargo:warning=db_ip error generating static region: {:?}",
+                        e
+                    );
+                }
             }
 
             #[cfg(feature = "include-country-code-lite")]
             {
-                let country_code_path =
-                    format!("{}/country_code_lite.bin", env::var("OUT_DIR").unwrap());
-                if let Err(e) =
-                    compress_lite::<db_ip_core::CountryCode>(&csv_path, &country_code_path)
-                {
+                let country_code_path = out_dir.join("country_code_lite.bin");
+                if let Err(e) = compress_lite(
+                    &lite,
+                    &cache_dir,
+                    &country_code_path,
+                    "country_code",
+                    &restriction,
+                    &mut country_codes,
+                    Some,
+                ) {
                     println!("cargo:warning=db_ip error embedding country code: {:?}", e);
                 }
+                #[cfg(feature = "static-lite")]
+                if let Err(e) = write_static::<db_ip_core::CountryCode>(
+                    &country_code_path,
+                    &out_dir.join("country_code_lite.rs"),
+                ) {
+                    println!(
+                        "cargo:warning=db_ip error generating static country code: {:?}",
+                        e
+                    );
+                }
             }
         }
     }
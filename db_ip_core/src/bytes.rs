@@ -0,0 +1,230 @@
+use crate::validate::UncheckedDbIpDatabase;
+use crate::{DbIpDatabase, InvalidDatabase, IpData};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt::{self, Display, Formatter};
+
+/// Identifies serialized databases.
+const MAGIC: [u8; 4] = *b"DBIP";
+/// Identifies serialized databases with a compressed body.
+pub(crate) const COMPRESSED_MAGIC: [u8; 4] = *b"DBIZ";
+/// Incremented whenever the serialized layout changes incompatibly.
+const VERSION: u16 = 1;
+/// `MAGIC`, `VERSION`, address families, `IpData::TYPE_ID`, body length and body CRC-32.
+pub(crate) const HEADER_LEN: usize = 4 + 2 + 2 + 4 + 8 + 4;
+
+/// Address families serialized in the body, since its layout depends on the enabled features.
+const FAMILIES: u16 = cfg!(feature = "ipv4") as u16 | (cfg!(feature = "ipv6") as u16) << 1;
+
+/// Errors that may arise when loading a [`DbIpDatabase`] from bytes.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FromBytesError {
+    /// The bytes don't start with the expected magic bytes, so aren't a serialized database.
+    InvalidMagic,
+    /// Serialized in a format version that this version of the crate can't read.
+    UnsupportedVersion(u16),
+    /// Serialized with different `ipv4` and `ipv6` features.
+    AddressFamilyMismatch,
+    /// Serialized with a different value type, identified by [`IpData::TYPE_ID`].
+    TypeMismatch { expected: u32, found: u32 },
+    /// The bytes are truncated, or have trailing data.
+    InvalidLength,
+    /// The bytes are corrupted.
+    ChecksumMismatch,
+    /// Bincode error, despite a valid header.
+    Bincode(bincode::Error),
+    /// Serialized by `DbIpDatabase::to_compressed_bytes`, but the `compress` feature is disabled.
+    CompressionDisabled,
+    /// The compressed body couldn't be decompressed, despite a valid header.
+    InvalidCompression,
+    /// The database was deserialized, but is invalid (see [`DbIpDatabase::validate`]).
+    Invalid(InvalidDatabase),
+}
+
+impl Display for FromBytesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "not a serialized database"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            Self::AddressFamilyMismatch => {
+                write!(f, "serialized with different ipv4/ipv6 features")
+            }
+            Self::TypeMismatch { expected, found } => write!(
+                f,
+                "serialized with value type {}, expected {}",
+                found, expected
+            ),
+            Self::InvalidLength => write!(f, "truncated or has trailing data"),
+            Self::ChecksumMismatch => write!(f, "checksum mismatch"),
+            Self::Bincode(e) => write!(f, "bincode error: {}", e),
+            Self::CompressionDisabled => {
+                write!(f, "compressed, but the compress feature is disabled")
+            }
+            Self::InvalidCompression => write!(f, "invalid compressed body"),
+            Self::Invalid(e) => write!(f, "invalid database: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FromBytesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bincode(e) => Some(e),
+            Self::Invalid(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<V: IpData + serde::Serialize> DbIpDatabase<V> {
+    /// Serializes the database, prefixed by a header that [`DbIpDatabase::from_bytes`] checks.
+    ///
+    /// The metadata isn't serialized. The value type is only identified by [`IpData::TYPE_ID`],
+    /// which is 0 for all types that don't override it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        with_header::<V>(MAGIC, &bincode::serialize(self).unwrap())
+    }
+}
+
+/// Prefixes a serialized body with the header.
+pub(crate) fn with_header<V: IpData>(magic: [u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+    bytes.extend_from_slice(&magic);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&FAMILIES.to_le_bytes());
+    bytes.extend_from_slice(&V::TYPE_ID.to_le_bytes());
+    bytes.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(body).to_le_bytes());
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+impl<V: IpData + serde::de::DeserializeOwned> DbIpDatabase<V> {
+    /// Deserializes a database serialized by [`DbIpDatabase::to_bytes`] (or
+    /// `DbIpDatabase::to_compressed_bytes`), with the same value type and address family
+    /// features.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FromBytesError> {
+        let compressed = match bytes.get(..MAGIC.len()) {
+            Some(magic) if magic == MAGIC => false,
+            Some(magic) if magic == COMPRESSED_MAGIC => true,
+            _ => return Err(FromBytesError::InvalidMagic),
+        };
+        let header = bytes
+            .get(..HEADER_LEN)
+            .ok_or(FromBytesError::InvalidLength)?;
+        let version = u16::from_le_bytes(header[4..6].try_into().unwrap());
+        if version != VERSION {
+            return Err(FromBytesError::UnsupportedVersion(version));
+        }
+        if u16::from_le_bytes(header[6..8].try_into().unwrap()) != FAMILIES {
+            return Err(FromBytesError::AddressFamilyMismatch);
+        }
+        let type_id = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if type_id != V::TYPE_ID {
+            return Err(FromBytesError::TypeMismatch {
+                expected: V::TYPE_ID,
+                found: type_id,
+            });
+        }
+        let body = &bytes[HEADER_LEN..];
+        if u64::from_le_bytes(header[12..20].try_into().unwrap()) != body.len() as u64 {
+            return Err(FromBytesError::InvalidLength);
+        }
+        if u32::from_le_bytes(header[20..24].try_into().unwrap()) != crc32fast::hash(body) {
+            return Err(FromBytesError::ChecksumMismatch);
+        }
+        let unchecked = if compressed {
+            #[cfg(feature = "compress")]
+            {
+                crate::compress::decompress::<V>(body)?
+            }
+            #[cfg(not(feature = "compress"))]
+            return Err(FromBytesError::CompressionDisabled);
+        } else {
+            bincode::deserialize::<UncheckedDbIpDatabase<V>>(body)
+                .map_err(FromBytesError::Bincode)?
+        };
+        unchecked.validate().map_err(FromBytesError::Invalid)
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(
+    feature = "csv",
+    feature = "region",
+    feature = "ipv4",
+    feature = "ipv6"
+))]
+mod test {
+    use super::{with_header, FromBytesError, HEADER_LEN, MAGIC};
+    use crate::{AddressFamily, CountryCode, DbIpDatabase, InvalidDatabase, Region};
+
+    #[test]
+    fn round_trip() {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv"))
+                .unwrap();
+        let bytes = db.to_bytes();
+        let copy = DbIpDatabase::<CountryCode>::from_bytes(&bytes).unwrap();
+        assert!(copy.iter().eq(db.iter()));
+
+        assert!(matches!(
+            DbIpDatabase::<Region>::from_bytes(&bytes),
+            Err(FromBytesError::TypeMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(FromBytesError::InvalidLength)
+        ));
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&bytes[HEADER_LEN..]),
+            Err(FromBytesError::InvalidMagic)
+        ));
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&corrupted),
+            Err(FromBytesError::ChecksumMismatch)
+        ));
+        assert_eq!(
+            DbIpDatabase::<Region>::from_bytes(&corrupted)
+                .unwrap_err()
+                .to_string(),
+            "serialized with value type 1, expected 2"
+        );
+
+        let mut future = bytes;
+        future[4] = 2;
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&future),
+            Err(FromBytesError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        let us = CountryCode::from_str("US");
+        // Ipv4 starts and values, then Ipv6 starts and values.
+        let body = bincode::serialize(&(
+            vec![0u32, 2, 1],
+            vec![us, None, us],
+            Vec::<u128>::new(),
+            Vec::<Option<CountryCode>>::new(),
+        ))
+        .unwrap();
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&with_header::<CountryCode>(MAGIC, &body)),
+            Err(FromBytesError::Invalid(InvalidDatabase::Unsorted {
+                family: AddressFamily::Ipv4,
+                index: 2
+            }))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+//! Decomposition of address ranges into CIDR blocks.
+
+/// Iterates the fewest CIDR blocks, as `(network, prefix length)`, that exactly cover the
+/// inclusive range `start..=end` of an address family with `bits` bits (32 or 128).
+pub(crate) fn range_to_cidrs(
+    start: u128,
+    end: u128,
+    bits: u32,
+) -> impl Iterator<Item = (u128, u32)> {
+    let mut next = Some(start).filter(|&start| start <= end);
+
+    std::iter::from_fn(move || {
+        let start = next?;
+        // Largest block that `start` is aligned to...
+        let aligned = start.trailing_zeros().min(bits);
+        // ...that doesn't extend past `end`.
+        let remaining = end - start;
+        let fits = if remaining == u128::MAX {
+            128
+        } else {
+            127 - (remaining + 1).leading_zeros()
+        };
+        let host_bits = aligned.min(fits);
+
+        next = 1u128
+            .checked_shl(host_bits)
+            .and_then(|size| start.checked_add(size))
+            .filter(|&next| next <= end);
+        Some((start, bits - host_bits))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::range_to_cidrs;
+
+    #[test]
+    fn cidrs() {
+        let cidrs = |start: u128, end: u128, bits: u32| {
+            range_to_cidrs(start, end, bits).collect::<Vec<_>>()
+        };
+
+        assert_eq!(cidrs(0, u32::MAX as u128, 32), vec![(0, 0)]);
+        assert_eq!(cidrs(0, u128::MAX, 128), vec![(0, 0)]);
+        assert_eq!(cidrs(u128::MAX, u128::MAX, 128), vec![(u128::MAX, 128)]);
+        assert_eq!(cidrs(256, 511, 32), vec![(256, 24)]);
+        assert_eq!(cidrs(1, 6, 32), vec![(1, 32), (2, 31), (4, 31), (6, 32)]);
+        assert_eq!(cidrs(7, 6, 32), vec![]);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::bytes::{with_header, COMPRESSED_MAGIC};
+use crate::validate::UncheckedDbIpDatabase;
+use crate::{DbIpDatabase, DbIpDatabaseInner, FromBytesError, IpData};
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+
+/// DEFLATE compression level, favoring size over compression speed.
+const LEVEL: u8 = 10;
+
+/// Like [`DbIpDatabaseInner`], but with range starts delta encoded as LEB128 varints, since
+/// consecutive starts are usually close together.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CompactInner<T> {
+    starts: Vec<u8>,
+    values: T,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CompactDatabase<T> {
+    #[cfg(feature = "ipv4")]
+    v4: CompactInner<T>,
+    #[cfg(feature = "ipv6")]
+    v6: CompactInner<T>,
+}
+
+impl<V: IpData + serde::Serialize> DbIpDatabase<V> {
+    /// Like [`DbIpDatabase::to_bytes`], but with range starts delta encoded and the body
+    /// compressed with DEFLATE, for embedding in binaries where size matters.
+    /// [`DbIpDatabase::from_bytes`] decompresses it, which takes a few times longer.
+    pub fn to_compressed_bytes(&self) -> Vec<u8> {
+        let compact = CompactDatabase {
+            #[cfg(feature = "ipv4")]
+            v4: compact(&self.v4),
+            #[cfg(feature = "ipv6")]
+            v6: compact(&self.v6),
+        };
+        let body = bincode::serialize(&compact).unwrap();
+        let mut compressed = (body.len() as u64).to_le_bytes().to_vec();
+        compressed.extend(miniz_oxide::deflate::compress_to_vec(&body, LEVEL));
+        with_header::<V>(COMPRESSED_MAGIC, &compressed)
+    }
+}
+
+/// Inverse of [`DbIpDatabase::to_compressed_bytes`], given the body after the header.
+pub(crate) fn decompress<V: IpData + serde::de::DeserializeOwned>(
+    compressed: &[u8],
+) -> Result<UncheckedDbIpDatabase<V>, FromBytesError> {
+    let (len, deflated) = compressed
+        .get(..8)
+        .map(|len| compressed.split_at(len.len()))
+        .ok_or(FromBytesError::InvalidCompression)?;
+    let len = usize::try_from(u64::from_le_bytes(len.try_into().unwrap()))
+        .map_err(|_| FromBytesError::InvalidCompression)?;
+    let body = miniz_oxide::inflate::decompress_to_vec_with_limit(deflated, len)
+        .ok()
+        .filter(|body| body.len() == len)
+        .ok_or(FromBytesError::InvalidCompression)?;
+    let compact: CompactDatabase<Vec<Option<V>>> =
+        bincode::deserialize(&body).map_err(FromBytesError::Bincode)?;
+    Ok(UncheckedDbIpDatabase {
+        #[cfg(feature = "ipv4")]
+        v4: expand(compact.v4)?,
+        #[cfg(feature = "ipv6")]
+        v6: expand(compact.v6)?,
+    })
+}
+
+fn compact<IP: Copy + Into<u128>, V>(
+    inner: &DbIpDatabaseInner<IP, V>,
+) -> CompactInner<&[Option<V>]> {
+    let mut starts = Vec::new();
+    let mut previous = 0;
+    for &start in &inner.starts {
+        let start = start.into();
+        let mut delta = start - previous;
+        while delta >= 0x80 {
+            starts.push(delta as u8 | 0x80);
+            delta >>= 7;
+        }
+        starts.push(delta as u8);
+        previous = start;
+    }
+    CompactInner {
+        starts,
+        values: &inner.values,
+    }
+}
+
+fn expand<IP: TryFrom<u128>, V>(
+    compact: CompactInner<Vec<Option<V>>>,
+) -> Result<DbIpDatabaseInner<IP, V>, FromBytesError> {
+    let mut starts = Vec::with_capacity(compact.values.len());
+    let mut previous = 0u128;
+    let mut delta = 0u128;
+    let mut shift = 0;
+    for byte in compact.starts {
+        if shift >= 128 {
+            return Err(FromBytesError::InvalidCompression);
+        }
+        delta |= u128::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            previous = previous
+                .checked_add(delta)
+                .ok_or(FromBytesError::InvalidCompression)?;
+            starts.push(IP::try_from(previous).map_err(|_| FromBytesError::InvalidCompression)?);
+            delta = 0;
+            shift = 0;
+        }
+    }
+    if shift != 0 {
+        return Err(FromBytesError::InvalidCompression);
+    }
+    Ok(DbIpDatabaseInner {
+        starts,
+        values: compact.values,
+    })
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "csv", feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use crate::bytes::{with_header, COMPRESSED_MAGIC, HEADER_LEN};
+    use crate::{CountryCode, DbIpDatabase, FromBytesError};
+
+    #[test]
+    fn round_trip() {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv"))
+                .unwrap();
+        let bytes = db.to_compressed_bytes();
+        let copy = DbIpDatabase::<CountryCode>::from_bytes(&bytes).unwrap();
+        assert!(copy.iter().eq(db.iter()));
+
+        // The header is intact, so only the body is checked.
+        let truncated =
+            with_header::<CountryCode>(COMPRESSED_MAGIC, &bytes[HEADER_LEN..bytes.len() - 1]);
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_bytes(&truncated),
+            Err(FromBytesError::InvalidCompression)
+        ));
+    }
+}
@@ -0,0 +1,386 @@
+#[cfg(feature = "ipv4")]
+use crate::ip_v4_to_ne;
+#[cfg(feature = "ipv6")]
+use crate::ip_v6_to_ne;
+#[allow(unused_imports)]
+use crate::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use crate::IpData;
+use core::ops::RangeInclusive;
+
+/// A map of ip range to data, like [`DbIpDatabase`](crate::DbIpDatabase), that borrows its
+/// ranges instead of owning them.
+///
+/// Since it can be constructed in a `const` context, it can be a `static` that needs no
+/// deserialization or heap allocation (see `db_ip::global_country_database`).
+#[derive(Debug)]
+pub struct DbIpDatabaseRef<'a, V> {
+    #[cfg(feature = "ipv4")]
+    pub(crate) v4: RangesRef<'a, u32, V>,
+    #[cfg(feature = "ipv6")]
+    pub(crate) v6: RangesRef<'a, u128, V>,
+}
+
+impl<'a, V> DbIpDatabaseRef<'a, V> {
+    /// Borrows sorted, native endian range starts, and the value associated with each range
+    /// (until the start of the next). Slices of a disabled address family are ignored.
+    ///
+    /// # Panics
+    ///
+    /// If the starts and values of an address family differ in length. If the starts aren't
+    /// sorted in ascending order, lookups return unspecified results.
+    #[allow(unused_variables)]
+    pub const fn from_parts(
+        v4_starts: &'a [u32],
+        v4_values: &'a [Option<V>],
+        v6_starts: &'a [u128],
+        v6_values: &'a [Option<V>],
+    ) -> Self {
+        assert!(v4_starts.len() == v4_values.len());
+        assert!(v6_starts.len() == v6_values.len());
+        Self {
+            #[cfg(feature = "ipv4")]
+            v4: RangesRef {
+                starts: v4_starts,
+                values: v4_values,
+            },
+            #[cfg(feature = "ipv6")]
+            v6: RangesRef {
+                starts: v6_starts,
+                values: v6_values,
+            },
+        }
+    }
+}
+
+impl<'a, V: IpData> DbIpDatabaseRef<'a, V> {
+    /// Gets the value associated with an ip address, if any.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn get(&self, ip: &IpAddr) -> Option<V> {
+        match ip {
+            IpAddr::V4(v4) => self.get_v4(v4),
+            IpAddr::V6(v6) => self.get_v6(v6),
+        }
+    }
+
+    /// Gets the value associated with an Ipv4 address, if any.
+    #[cfg(feature = "ipv4")]
+    pub fn get_v4(&self, v4: &Ipv4Addr) -> Option<V> {
+        self.v4.lookup(&ip_v4_to_ne(v4))
+    }
+
+    /// Gets the value associated with an Ipv6 address, if any.
+    #[cfg(feature = "ipv6")]
+    pub fn get_v6(&self, v6: &Ipv6Addr) -> Option<V> {
+        self.v6.lookup(&ip_v6_to_ne(v6))
+    }
+
+    /// Gets the value associated with an ip address, if any, along with the address range it
+    /// applies to.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn get_range(&self, ip: &IpAddr) -> Option<(RangeInclusive<IpAddr>, V)> {
+        match ip {
+            IpAddr::V4(v4) => self
+                .get_range_v4(v4)
+                .map(|(range, v)| (IpAddr::V4(*range.start())..=IpAddr::V4(*range.end()), v)),
+            IpAddr::V6(v6) => self
+                .get_range_v6(v6)
+                .map(|(range, v)| (IpAddr::V6(*range.start())..=IpAddr::V6(*range.end()), v)),
+        }
+    }
+
+    /// Gets the value associated with an Ipv4 address, if any, along with the address range it
+    /// applies to.
+    #[cfg(feature = "ipv4")]
+    pub fn get_range_v4(&self, v4: &Ipv4Addr) -> Option<(RangeInclusive<Ipv4Addr>, V)> {
+        self.v4
+            .lookup_range(&ip_v4_to_ne(v4))
+            .map(|(start, next, v)| {
+                let end = next.map(|next| next - 1).unwrap_or(u32::MAX);
+                (Ipv4Addr::from(start)..=Ipv4Addr::from(end), v)
+            })
+    }
+
+    /// Gets the value associated with an Ipv6 address, if any, along with the address range it
+    /// applies to.
+    #[cfg(feature = "ipv6")]
+    pub fn get_range_v6(&self, v6: &Ipv6Addr) -> Option<(RangeInclusive<Ipv6Addr>, V)> {
+        self.v6
+            .lookup_range(&ip_v6_to_ne(v6))
+            .map(|(start, next, v)| {
+                let end = next.map(|next| next - 1).unwrap_or(u128::MAX);
+                (Ipv6Addr::from(start)..=Ipv6Addr::from(end), v)
+            })
+    }
+
+    /// Returns number of ranges/values stored for both Ipv4 and Ipv6 addresses.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn len(&self) -> usize {
+        self.len_v4() + self.len_v6()
+    }
+
+    /// Returns number of ranges/values stored for Ipv4 addresses.
+    #[cfg(feature = "ipv4")]
+    pub fn len_v4(&self) -> usize {
+        self.v4.values.len()
+    }
+
+    /// Returns number of ranges/values stored for Ipv6 addresses.
+    #[cfg(feature = "ipv6")]
+    pub fn len_v6(&self) -> usize {
+        self.v6.values.len()
+    }
+
+    /// Returns whether no ranges/values are stored for either Ipv4 or Ipv6 addresses.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn is_empty(&self) -> bool {
+        self.is_empty_v4() && self.is_empty_v6()
+    }
+
+    /// Returns whether no ranges/values are stored for Ipv4 addresses.
+    #[cfg(feature = "ipv4")]
+    pub fn is_empty_v4(&self) -> bool {
+        self.v4.values.is_empty()
+    }
+
+    /// Returns whether no ranges/values are stored for Ipv6 addresses.
+    #[cfg(feature = "ipv6")]
+    pub fn is_empty_v6(&self) -> bool {
+        self.v6.values.is_empty()
+    }
+
+    /// Iterates the address ranges that have a value, in ascending order, Ipv4 first.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn iter(self) -> impl Iterator<Item = (RangeInclusive<IpAddr>, V)> + 'a {
+        let v4 = self
+            .iter_v4()
+            .map(|(range, v)| (IpAddr::V4(*range.start())..=IpAddr::V4(*range.end()), v));
+        let v6 = self
+            .iter_v6()
+            .map(|(range, v)| (IpAddr::V6(*range.start())..=IpAddr::V6(*range.end()), v));
+        v4.chain(v6)
+    }
+
+    /// Like [`DbIpDatabaseRef::iter`], but starts with the range containing `ip` (or the first
+    /// one after it), which is found by binary search.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn iter_from(
+        mut self,
+        ip: &IpAddr,
+    ) -> impl Iterator<Item = (RangeInclusive<IpAddr>, V)> + 'a {
+        match ip {
+            IpAddr::V4(v4) => self.v4 = self.v4.skip_to(&ip_v4_to_ne(v4)),
+            IpAddr::V6(v6) => {
+                self.v4 = RangesRef {
+                    starts: &[],
+                    values: &[],
+                };
+                self.v6 = self.v6.skip_to(&ip_v6_to_ne(v6));
+            }
+        }
+        self.iter()
+    }
+
+    /// Iterates the Ipv4 address ranges that have a value, in ascending order.
+    #[cfg(feature = "ipv4")]
+    pub fn iter_v4(self) -> impl Iterator<Item = (RangeInclusive<Ipv4Addr>, V)> + 'a {
+        self.v4.ranges().map(|(start, next, v)| {
+            let end = next.map(|next| next - 1).unwrap_or(u32::MAX);
+            (Ipv4Addr::from(start)..=Ipv4Addr::from(end), v)
+        })
+    }
+
+    /// Iterates the Ipv6 address ranges that have a value, in ascending order.
+    #[cfg(feature = "ipv6")]
+    pub fn iter_v6(self) -> impl Iterator<Item = (RangeInclusive<Ipv6Addr>, V)> + 'a {
+        self.v6.ranges().map(|(start, next, v)| {
+            let end = next.map(|next| next - 1).unwrap_or(u128::MAX);
+            (Ipv6Addr::from(start)..=Ipv6Addr::from(end), v)
+        })
+    }
+}
+
+/// Decodes big endian Ipv4 range starts at compile time, for `static` data generated by a build
+/// step.
+#[doc(hidden)]
+pub const fn static_starts_v4<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    assert!(bytes.len() == N * 4);
+    let mut starts = [0; N];
+    let mut i = 0;
+    while i < N {
+        let b = i * 4;
+        starts[i] = u32::from_be_bytes([bytes[b], bytes[b + 1], bytes[b + 2], bytes[b + 3]]);
+        i += 1;
+    }
+    starts
+}
+
+/// Decodes big endian Ipv6 range starts at compile time, for `static` data generated by a build
+/// step.
+#[doc(hidden)]
+pub const fn static_starts_v6<const N: usize>(bytes: &[u8]) -> [u128; N] {
+    assert!(bytes.len() == N * 16);
+    let mut starts = [0; N];
+    let mut i = 0;
+    while i < N {
+        let mut start = 0;
+        let mut j = 0;
+        while j < 16 {
+            start = (start << 8) | bytes[i * 16 + j] as u128;
+            j += 1;
+        }
+        starts[i] = start;
+        i += 1;
+    }
+    starts
+}
+
+impl<V> Clone for DbIpDatabaseRef<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for DbIpDatabaseRef<'_, V> {}
+
+/// Borrows either Ipv4 or Ipv6 addresses/values.
+#[derive(Debug)]
+pub(crate) struct RangesRef<'a, IP, V> {
+    /// Sorted address range starts, in native endian.
+    pub starts: &'a [IP],
+    /// Value associated with each address range.
+    pub values: &'a [Option<V>],
+}
+
+impl<'a, IP: Ord + Copy, V: IpData> RangesRef<'a, IP, V> {
+    /// Lookup value associated with native endian IP address, based on range.
+    fn lookup(&self, ip: &IP) -> Option<V> {
+        debug_assert_eq!(self.starts.len(), self.values.len());
+
+        match self.starts.binary_search(ip) {
+            Ok(idx) => self.values[idx],
+            Err(idx) => {
+                if idx > 0 && self.starts.get(idx).map(|end| ip < end).unwrap_or(true) {
+                    self.values.get(idx - 1).copied().unwrap_or(None)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Lookup value associated with native endian IP address, along with the start of its range
+    /// and the start of the next range (if any).
+    fn lookup_range(&self, ip: &IP) -> Option<(IP, Option<IP>, V)> {
+        let idx = match self.starts.binary_search(ip) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        let value = self.values.get(idx).copied().flatten()?;
+        Some((self.starts[idx], self.starts.get(idx + 1).copied(), value))
+    }
+
+    /// Skips the ranges before the one containing native endian IP address.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn skip_to(self, ip: &IP) -> Self {
+        let idx = match self.starts.binary_search(ip) {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        };
+        Self {
+            starts: &self.starts[idx..],
+            values: &self.values[idx..],
+        }
+    }
+
+    /// Iterates ranges with a value, as their start and the start of the next range (if any).
+    fn ranges(self) -> impl Iterator<Item = (IP, Option<IP>, V)> + 'a {
+        let starts = self.starts;
+        starts
+            .iter()
+            .zip(self.values.iter())
+            .enumerate()
+            .filter_map(move |(i, (&start, value))| {
+                value.map(|v| (start, starts.get(i + 1).copied(), v))
+            })
+    }
+}
+
+impl<IP, V> Clone for RangesRef<'_, IP, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<IP, V> Copy for RangesRef<'_, IP, V> {}
+
+#[cfg(test)]
+mod test {
+    use crate::{static_starts_v4, static_starts_v6};
+    #[cfg(feature = "ipv4")]
+    use crate::{CountryCode, DbIpDatabaseRef};
+
+    #[test]
+    #[cfg(feature = "ipv4")]
+    fn from_parts() {
+        static STARTS: [u32; 3] = static_starts_v4(&[1, 0, 0, 0, 1, 0, 1, 0, 1, 0, 2, 0]);
+        static VALUES: [Option<CountryCode>; 3] = CountryCode::static_values(b"AU\0\0US");
+        static DB: DbIpDatabaseRef<'static, CountryCode> =
+            DbIpDatabaseRef::from_parts(&STARTS, &VALUES, &[], &[]);
+
+        assert_eq!(DB.get_v4(&"0.255.255.255".parse().unwrap()), None);
+        assert_eq!(
+            DB.get_v4(&"1.0.0.1".parse().unwrap()),
+            CountryCode::from_str("AU")
+        );
+        assert_eq!(DB.get_v4(&"1.0.1.1".parse().unwrap()), None);
+        assert_eq!(
+            DB.get_range_v4(&"255.255.255.255".parse().unwrap()),
+            Some((
+                "1.0.2.0".parse().unwrap()..="255.255.255.255".parse().unwrap(),
+                CountryCode::from_str("US").unwrap()
+            ))
+        );
+        assert_eq!(DB.len_v4(), 3);
+        assert!(!DB.is_empty_v4());
+        assert_eq!(DB.iter_v4().count(), 2);
+        #[cfg(feature = "ipv6")]
+        {
+            assert!(DB.is_empty_v6());
+            assert!(!DB.is_empty());
+        }
+    }
+
+    #[test]
+    fn static_starts() {
+        let mut bytes = [0; 32];
+        bytes[15] = 1;
+        bytes[16] = 0x2a;
+        assert_eq!(static_starts_v6::<2>(&bytes), [1, 0x2a << 120]);
+        assert_eq!(
+            static_starts_v4::<2>(&[1, 2, 3, 4, 255, 255, 255, 255]),
+            [0x01020304, u32::MAX]
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "csv", feature = "ipv4", feature = "ipv6"))]
+    fn iter_from() {
+        let db = crate::DbIpDatabase::<CountryCode>::from_csv_str(include_str!(
+            "../../test_country_data.csv"
+        ))
+        .unwrap();
+        for ip in [
+            "0.0.0.0",
+            "1.0.0.7",
+            "1.0.1.0",
+            "255.255.255.255",
+            "::",
+            "2a07:7ec5:8226::1",
+        ] {
+            let ip: std::net::IpAddr = ip.parse().unwrap();
+            let expected = db.iter().skip_while(|(range, _)| *range.end() < ip);
+            assert!(db.iter_from(&ip).eq(expected), "{}", ip);
+        }
+    }
+}
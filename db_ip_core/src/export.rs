@@ -0,0 +1,274 @@
+//! Exporters of address ranges to the configuration formats of proxies and firewalls.
+
+use crate::cidr::range_to_cidrs;
+use crate::{DbIpDatabase, IpData};
+use std::fmt::Display;
+use std::io::{self, Write};
+#[allow(unused_imports)]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+impl<V: IpData> DbIpDatabase<V> {
+    /// Writes the values for which `filter` returns `true` as an nginx `geo` block, which sets
+    /// `$variable` to the value associated with the client address.
+    ///
+    /// ```text
+    /// geo $variable {
+    ///     1.0.0.0/24 AU;
+    /// }
+    /// ```
+    pub fn write_nginx_geo<W: Write>(
+        &self,
+        mut writer: W,
+        variable: &str,
+        filter: impl Fn(&V) -> bool,
+    ) -> io::Result<()>
+    where
+        V: Display,
+    {
+        writeln!(writer, "geo ${} {{", variable)?;
+        for (network, prefix, value) in self.cidrs(&filter) {
+            writeln!(writer, "    {}/{} {};", network, prefix, value)?;
+        }
+        writeln!(writer, "}}")
+    }
+
+    /// Writes the values for which `filter` returns `true` as an HAProxy map file, for use
+    /// with the `map_ip` converter.
+    ///
+    /// ```text
+    /// 1.0.0.0/24 AU
+    /// ```
+    pub fn write_haproxy_map<W: Write>(
+        &self,
+        mut writer: W,
+        filter: impl Fn(&V) -> bool,
+    ) -> io::Result<()>
+    where
+        V: Display,
+    {
+        for (network, prefix, value) in self.cidrs(&filter) {
+            writeln!(writer, "{}/{} {}", network, prefix, value)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the ranges whose values `filter` returns `true` for as an `ipset restore` script,
+    /// creating the `hash:net` sets `{set_name}_v4` and `{set_name}_v6`.
+    pub fn write_ipset<W: Write>(
+        &self,
+        mut writer: W,
+        set_name: &str,
+        filter: impl Fn(&V) -> bool,
+    ) -> io::Result<()> {
+        for (suffix, family, networks) in self.set_networks(&filter) {
+            writeln!(
+                writer,
+                "create {}_{} hash:net family {} maxelem {} -exist",
+                set_name,
+                suffix,
+                family,
+                networks.len().max(65536)
+            )?;
+            for (network, prefix) in networks {
+                writeln!(
+                    writer,
+                    "add {}_{} {}/{} -exist",
+                    set_name, suffix, network, prefix
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the ranges whose values `filter` returns `true` for as an `nft -f` script, adding
+    /// the interval sets `{set_name}_v4` and `{set_name}_v6` to `table` (e.g. `"inet filter"`).
+    pub fn write_nftables_set<W: Write>(
+        &self,
+        mut writer: W,
+        table: &str,
+        set_name: &str,
+        filter: impl Fn(&V) -> bool,
+    ) -> io::Result<()> {
+        for (suffix, family, networks) in self.set_networks(&filter) {
+            let ty = if family == "inet" {
+                "ipv4_addr"
+            } else {
+                "ipv6_addr"
+            };
+            writeln!(
+                writer,
+                "add set {} {}_{} {{ type {}; flags interval; }}",
+                table, set_name, suffix, ty
+            )?;
+            if networks.is_empty() {
+                continue;
+            }
+            write!(writer, "add element {} {}_{} {{", table, set_name, suffix)?;
+            for (i, (network, prefix)) in networks.into_iter().enumerate() {
+                let separator = if i == 0 { " " } else { ", " };
+                write!(writer, "{}{}/{}", separator, network, prefix)?;
+            }
+            writeln!(writer, " }}")?;
+        }
+        Ok(())
+    }
+
+    /// CIDR blocks of the ranges whose values pass `filter`, along with their value.
+    fn cidrs<'a>(
+        &'a self,
+        filter: &'a impl Fn(&V) -> bool,
+    ) -> impl Iterator<Item = (IpAddr, u32, V)> + 'a {
+        #[cfg(feature = "ipv4")]
+        let v4 = self.iter_v4().flat_map(|(range, value)| {
+            range_to_cidrs(
+                u32::from(*range.start()) as u128,
+                u32::from(*range.end()) as u128,
+                32,
+            )
+            .map(move |(network, prefix)| {
+                (IpAddr::V4(Ipv4Addr::from(network as u32)), prefix, value)
+            })
+        });
+        #[cfg(not(feature = "ipv4"))]
+        let v4 = std::iter::empty();
+
+        #[cfg(feature = "ipv6")]
+        let v6 = self.iter_v6().flat_map(|(range, value)| {
+            range_to_cidrs(u128::from(*range.start()), u128::from(*range.end()), 128)
+                .map(move |(network, prefix)| (IpAddr::V6(Ipv6Addr::from(network)), prefix, value))
+        });
+        #[cfg(not(feature = "ipv6"))]
+        let v6 = std::iter::empty();
+
+        v4.chain(v6).filter(move |(_, _, value)| filter(value))
+    }
+
+    /// CIDR blocks of the ranges whose values pass `filter`, with adjacent ranges coalesced, as
+    /// `(set name suffix, ipset family, blocks)` for each enabled address family.
+    #[allow(clippy::type_complexity)]
+    fn set_networks(
+        &self,
+        filter: &impl Fn(&V) -> bool,
+    ) -> Vec<(&'static str, &'static str, Vec<(IpAddr, u32)>)> {
+        #[allow(unused_mut)]
+        let mut sets = Vec::new();
+
+        #[cfg(feature = "ipv4")]
+        {
+            let ranges = self
+                .iter_v4()
+                .filter(|(_, value)| filter(value))
+                .map(|(range, _)| {
+                    (
+                        u32::from(*range.start()) as u128,
+                        u32::from(*range.end()) as u128,
+                    )
+                });
+            let networks = coalesce(ranges)
+                .flat_map(|(start, end)| range_to_cidrs(start, end, 32))
+                .map(|(network, prefix)| (IpAddr::V4(Ipv4Addr::from(network as u32)), prefix))
+                .collect();
+            sets.push(("v4", "inet", networks));
+        }
+
+        #[cfg(feature = "ipv6")]
+        {
+            let ranges = self
+                .iter_v6()
+                .filter(|(_, value)| filter(value))
+                .map(|(range, _)| (u128::from(*range.start()), u128::from(*range.end())));
+            let networks = coalesce(ranges)
+                .flat_map(|(start, end)| range_to_cidrs(start, end, 128))
+                .map(|(network, prefix)| (IpAddr::V6(Ipv6Addr::from(network)), prefix))
+                .collect();
+            sets.push(("v6", "inet6", networks));
+        }
+
+        sets
+    }
+}
+
+/// Merges adjacent inclusive ranges of an ascending iterator.
+fn coalesce(ranges: impl Iterator<Item = (u128, u128)>) -> impl Iterator<Item = (u128, u128)> {
+    let mut ranges = ranges.peekable();
+    std::iter::from_fn(move || {
+        let (start, mut end) = ranges.next()?;
+        while let Some(&(next_start, next_end)) = ranges.peek() {
+            if end.checked_add(1) != Some(next_start) {
+                break;
+            }
+            end = next_end;
+            ranges.next();
+        }
+        Some((start, end))
+    })
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "csv", feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use crate::{CountryCode, DbIpDatabase};
+
+    const CSV: &str = "\
+1.0.0.0,1.0.0.255,AU
+1.0.1.0,1.0.2.255,US
+1.0.3.0,1.0.3.255,CA
+2a07:7ec5:8225::,2a07:7ec5:8225:ffff:ffff:ffff:ffff:ffff,US
+";
+
+    fn db() -> DbIpDatabase<CountryCode> {
+        DbIpDatabase::from_csv_str(CSV).unwrap()
+    }
+
+    fn north_america(cc: &CountryCode) -> bool {
+        matches!(cc.as_str(), "US" | "CA")
+    }
+
+    fn export(f: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn nginx_geo() {
+        let db = db();
+        assert_eq!(
+            export(|out| db.write_nginx_geo(out, "country", |_| true)),
+            "geo $country {\n    1.0.0.0/24 AU;\n    1.0.1.0/24 US;\n    1.0.2.0/24 US;\n    1.0.3.0/24 CA;\n    2a07:7ec5:8225::/48 US;\n}\n"
+        );
+    }
+
+    #[test]
+    fn haproxy_map() {
+        let db = db();
+        assert_eq!(
+            export(|out| db.write_haproxy_map(out, north_america)),
+            "1.0.1.0/24 US\n1.0.2.0/24 US\n1.0.3.0/24 CA\n2a07:7ec5:8225::/48 US\n"
+        );
+    }
+
+    #[test]
+    fn ipset() {
+        let db = db();
+        assert_eq!(
+            export(|out| db.write_ipset(out, "na", north_america)),
+            "create na_v4 hash:net family inet maxelem 65536 -exist\n\
+             add na_v4 1.0.1.0/24 -exist\n\
+             add na_v4 1.0.2.0/23 -exist\n\
+             create na_v6 hash:net family inet6 maxelem 65536 -exist\n\
+             add na_v6 2a07:7ec5:8225::/48 -exist\n"
+        );
+    }
+
+    #[test]
+    fn nftables_set() {
+        let db = db();
+        assert_eq!(
+            export(|out| db.write_nftables_set(out, "inet filter", "au", |cc| cc.as_str() == "AU")),
+            "add set inet filter au_v4 { type ipv4_addr; flags interval; }\n\
+             add element inet filter au_v4 { 1.0.0.0/24 }\n\
+             add set inet filter au_v6 { type ipv6_addr; flags interval; }\n"
+        );
+    }
+}
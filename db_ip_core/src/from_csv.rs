@@ -0,0 +1,159 @@
+use crate::{DbIpDatabase, DbIpDatabaseBuilder, FromCsvError, IpData};
+use core::str::FromStr;
+#[cfg(feature = "gzip")]
+use std::fs::File;
+use std::io::Read;
+#[cfg(feature = "gzip")]
+use std::io::{BufRead, BufReader};
+use std::net::IpAddr;
+
+/// First two bytes of any gzip stream.
+#[cfg(feature = "gzip")]
+pub(crate) const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Loads a [`DbIpDatabase`], or a tuple of up to four databases with different value types,
+/// from CSV data. A tuple is loaded in one pass, which takes about as long as loading one of its
+/// databases.
+///
+/// ```rust
+/// # #[cfg(feature = "region")] {
+/// use db_ip_core::{CountryCode, DbIpDatabase, FromCsv, Region};
+///
+/// let (country_codes, regions) = <(DbIpDatabase<CountryCode>, DbIpDatabase<Region>)>::from_csv_str(
+///     include_str!("../../test_country_data.csv"),
+/// )
+/// .unwrap();
+/// # }
+/// ```
+pub trait FromCsv: Sized {
+    /// Load from CSV file contained in string.
+    fn from_csv_str(csv: &str) -> Result<Self, FromCsvError> {
+        Self::from_csv_reader(csv.as_bytes())
+    }
+
+    /// Load from CSV file reader.
+    fn from_csv_reader<R: Read>(reader: R) -> Result<Self, FromCsvError> {
+        let reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(reader);
+
+        Self::from_csv_records(reader)
+    }
+
+    /// Load from CSV file contained in file.
+    ///
+    /// With the `gzip` feature, gzip-compressed files (such as the `.csv.gz` files served by
+    /// db-ip.com) are detected by their magic bytes and decompressed automatically.
+    fn from_csv_file(path: &str) -> Result<Self, FromCsvError> {
+        #[cfg(feature = "gzip")]
+        {
+            let file = File::open(path).map_err(|e| FromCsvError::Csv(e.into()))?;
+            let mut reader = BufReader::new(file);
+            let is_gzip = reader
+                .fill_buf()
+                .map_err(|e| FromCsvError::Csv(e.into()))?
+                .starts_with(&GZIP_MAGIC);
+
+            if is_gzip {
+                Self::from_csv_gz_reader(reader)
+            } else {
+                Self::from_csv_reader(reader)
+            }
+        }
+
+        #[cfg(not(feature = "gzip"))]
+        {
+            let reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_path(path)
+                .map_err(FromCsvError::Csv)?;
+
+            Self::from_csv_records(reader)
+        }
+    }
+
+    /// Load from gzip-compressed CSV file reader.
+    #[cfg(feature = "gzip")]
+    fn from_csv_gz_reader<R: Read>(reader: R) -> Result<Self, FromCsvError> {
+        Self::from_csv_reader(flate2::read::MultiGzDecoder::new(reader))
+    }
+
+    /// Load from gzip-compressed CSV file, e.g. `dbip-country-lite-2022-02.csv.gz`.
+    #[cfg(feature = "gzip")]
+    fn from_csv_gz_file(path: &str) -> Result<Self, FromCsvError> {
+        let file = File::open(path).map_err(|e| FromCsvError::Csv(e.into()))?;
+        Self::from_csv_gz_reader(BufReader::new(file))
+    }
+
+    #[doc(hidden)]
+    fn from_csv_records<R: Read>(reader: csv::Reader<R>) -> Result<Self, FromCsvError>;
+}
+
+impl<V: IpData> FromCsv for DbIpDatabase<V> {
+    fn from_csv_records<R: Read>(reader: csv::Reader<R>) -> Result<Self, FromCsvError> {
+        <(Self,)>::from_csv_records(reader).map(|(db,)| db)
+    }
+}
+
+/// Parses the address range of a record, which values are derived from.
+fn parse_range(record: &csv::StringRecord) -> Result<(IpAddr, IpAddr), FromCsvError> {
+    let begin = IpAddr::from_str(&record[0]).map_err(FromCsvError::AddrParse)?;
+    let end = IpAddr::from_str(&record[1]).map_err(FromCsvError::AddrParse)?;
+    Ok((begin, end))
+}
+
+macro_rules! impl_from_csv {
+    ($($v:ident $idx:tt),+) => {
+        impl<$($v: IpData),+> FromCsv for ($(DbIpDatabase<$v>,)+) {
+            fn from_csv_records<R: Read>(mut reader: csv::Reader<R>) -> Result<Self, FromCsvError> {
+                let mut builders = ($(DbIpDatabaseBuilder::<$v>::new(),)+);
+
+                for record in reader.records() {
+                    let record = record.map_err(FromCsvError::Csv)?;
+
+                    // Only parsed if any value is derived from the record.
+                    let mut range = None;
+                    $(
+                        if let Some(value) = $v::from_record(&record)? {
+                            let (begin, end) = match range {
+                                Some(range) => range,
+                                None => *range.insert(parse_range(&record)?),
+                            };
+                            builders.$idx.push(begin, end, value)?;
+                        }
+                    )+
+                }
+
+                Ok(($(builders.$idx.build(),)+))
+            }
+        }
+    };
+}
+
+impl_from_csv!(A 0);
+impl_from_csv!(A 0, B 1);
+impl_from_csv!(A 0, B 1, C 2);
+impl_from_csv!(A 0, B 1, C 2, D 3);
+
+#[cfg(test)]
+#[cfg(all(feature = "region", feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use crate::{CountryCode, DbIpDatabase, FromCsv, Region};
+
+    #[test]
+    fn tuple() {
+        let csv = include_str!("../../test_country_data.csv");
+        let (country_codes, regions, again) = <(
+            DbIpDatabase<CountryCode>,
+            DbIpDatabase<Region>,
+            DbIpDatabase<CountryCode>,
+        )>::from_csv_str(csv)
+        .unwrap();
+
+        let expected = DbIpDatabase::<CountryCode>::from_csv_str(csv).unwrap();
+        assert!(country_codes.iter().eq(expected.iter()));
+        assert!(again.iter().eq(expected.iter()));
+        let expected = DbIpDatabase::<Region>::from_csv_str(csv).unwrap();
+        assert!(regions.iter().eq(expected.iter()));
+    }
+}
@@ -0,0 +1,276 @@
+//! Importer for MaxMind GeoLite2 Country/City CSV files.
+
+use crate::{CountryCode, DbIpDatabase, DbIpDatabaseBuilder, FromCsvError, IpData, IpRecord};
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+impl<V: IpData> DbIpDatabase<V> {
+    /// Load from GeoLite2 CSV readers, by joining the `network` of each blocks file (e.g.
+    /// `GeoLite2-Country-Blocks-IPv4.csv` and `GeoLite2-Country-Blocks-IPv6.csv`) with the
+    /// `country_iso_code` of the locations file (e.g. `GeoLite2-Country-Locations-en.csv`).
+    ///
+    /// Networks are attributed to their `geoname_id`, falling back to their
+    /// `registered_country_geoname_id` if it is missing or not in the locations file. Networks
+    /// with neither, and locations without a country (such as continents), have no value.
+    pub fn from_geolite2_csv_readers<B: Read, L: Read>(
+        blocks: impl IntoIterator<Item = B>,
+        locations: L,
+    ) -> Result<Self, FromCsvError> {
+        let values = read_locations(locations)?;
+        let mut builder = DbIpDatabaseBuilder::new();
+
+        for blocks in blocks {
+            let mut reader = csv::Reader::from_reader(blocks);
+            let headers = reader.headers().map_err(FromCsvError::Csv)?;
+            let network_idx = column(headers, "network")?;
+            let geoname_idx = column(headers, "geoname_id")?;
+            let registered_idx = column(headers, "registered_country_geoname_id")?;
+
+            for record in reader.records() {
+                let record = record.map_err(FromCsvError::Csv)?;
+                let mut value = None;
+                for id in [geoname_idx, registered_idx]
+                    .iter()
+                    .filter_map(|&idx| record.get(idx))
+                    .filter(|id| !id.is_empty())
+                {
+                    let id = u32::from_str(id).map_err(|_| FromCsvError::InvalidRecord)?;
+                    if let Some(&location) = values.get(&id) {
+                        value = location;
+                        break;
+                    }
+                }
+
+                if let Some(value) = value {
+                    let network = record.get(network_idx).ok_or(FromCsvError::InvalidRecord)?;
+                    let (begin, end) = parse_network(network)?;
+                    builder.push(begin, end, value)?;
+                }
+            }
+        }
+
+        Ok(builder.build_bounded())
+    }
+
+    /// Load from GeoLite2 CSV files. See [`DbIpDatabase::from_geolite2_csv_readers`].
+    pub fn from_geolite2_csv_files(blocks: &[&str], locations: &str) -> Result<Self, FromCsvError> {
+        let blocks = blocks
+            .iter()
+            .map(std::fs::File::open)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| FromCsvError::Csv(e.into()))?;
+        let locations = std::fs::File::open(locations).map_err(|e| FromCsvError::Csv(e.into()))?;
+
+        Self::from_geolite2_csv_readers(
+            blocks.into_iter().map(std::io::BufReader::new),
+            std::io::BufReader::new(locations),
+        )
+    }
+}
+
+/// Maps each `geoname_id` of a locations file to the value derived from its location.
+fn read_locations<V: IpData, L: Read>(
+    locations: L,
+) -> Result<HashMap<u32, Option<V>>, FromCsvError> {
+    let mut reader = csv::Reader::from_reader(locations);
+    let headers = reader.headers().map_err(FromCsvError::Csv)?;
+    let geoname_idx = column(headers, "geoname_id")?;
+    let country_idx = column(headers, "country_iso_code")?;
+    // Only present in some editions.
+    let continent_idx = column(headers, "continent_code").ok();
+    let subdivision_idx = column(headers, "subdivision_1_name").ok();
+    let city_idx = column(headers, "city_name").ok();
+
+    let mut values = HashMap::new();
+    for record in reader.records() {
+        let record = record.map_err(FromCsvError::Csv)?;
+        let geoname_id = record.get(geoname_idx).ok_or(FromCsvError::InvalidRecord)?;
+        let geoname_id = u32::from_str(geoname_id).map_err(|_| FromCsvError::InvalidRecord)?;
+        let value = match record.get(country_idx) {
+            Some(country_code) if !country_code.is_empty() => {
+                let field = |idx: Option<usize>| {
+                    idx.and_then(|idx| record.get(idx))
+                        .filter(|field| !field.is_empty())
+                };
+                V::from_ip_record(&IpRecord {
+                    continent_code: field(continent_idx),
+                    subdivision: field(subdivision_idx),
+                    city: field(city_idx),
+                    ..IpRecord::from_country_code(
+                        CountryCode::from_str(country_code).ok_or(FromCsvError::InvalidRecord)?,
+                    )
+                })
+            }
+            _ => None,
+        };
+        values.insert(geoname_id, value);
+    }
+    Ok(values)
+}
+
+fn column(headers: &csv::StringRecord, name: &str) -> Result<usize, FromCsvError> {
+    headers
+        .iter()
+        .position(|header| header == name)
+        .ok_or(FromCsvError::InvalidRecord)
+}
+
+/// Parses CIDR notation, e.g. `1.0.0.0/24`, into an inclusive range.
+fn parse_network(network: &str) -> Result<(IpAddr, IpAddr), FromCsvError> {
+    let (addr, prefix) = network.split_once('/').ok_or(FromCsvError::InvalidRecord)?;
+    let addr = IpAddr::from_str(addr).map_err(FromCsvError::AddrParse)?;
+    let prefix = u32::from_str(prefix).map_err(|_| FromCsvError::InvalidRecord)?;
+
+    match addr {
+        IpAddr::V4(v4) => {
+            let host_mask = u32::MAX
+                .checked_shr(prefix)
+                .ok_or(FromCsvError::InvalidRecord)?;
+            let begin = u32::from(v4) & !host_mask;
+            Ok((
+                IpAddr::V4(Ipv4Addr::from(begin)),
+                IpAddr::V4(Ipv4Addr::from(begin | host_mask)),
+            ))
+        }
+        IpAddr::V6(v6) => {
+            let host_mask = u128::MAX
+                .checked_shr(prefix)
+                .ok_or(FromCsvError::InvalidRecord)?;
+            let begin = u128::from(v6) & !host_mask;
+            Ok((
+                IpAddr::V6(Ipv6Addr::from(begin)),
+                IpAddr::V6(Ipv6Addr::from(begin | host_mask)),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_network;
+    #[allow(unused_imports)]
+    use crate::{CountryCode, DbIpDatabase, FromCsvError};
+
+    #[cfg(feature = "ipv4")]
+    const LOCATIONS: &str = "\
+geoname_id,locale_code,continent_code,continent_name,country_iso_code,country_name,is_in_european_union
+2077456,en,OC,Oceania,AU,Australia,0
+6252001,en,NA,\"North America\",US,\"United States\",0
+6255148,en,EU,Europe,,,0
+";
+
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    const BLOCKS_V4: &str = "\
+network,geoname_id,registered_country_geoname_id,represented_country_geoname_id,is_anonymous_proxy,is_satellite_provider
+1.0.0.0/24,2077456,2077456,,0,0
+1.0.1.0/24,,6252001,,0,0
+1.0.2.0/23,6255148,,,0,0
+1.0.4.0/22,,,,1,0
+";
+
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    const BLOCKS_V6: &str = "\
+network,geoname_id,registered_country_geoname_id,represented_country_geoname_id,is_anonymous_proxy,is_satellite_provider
+2001:200::/32,6252001,6252001,,0,0
+";
+
+    #[test]
+    fn network() {
+        assert_eq!(
+            parse_network("1.0.2.0/23").unwrap(),
+            ("1.0.2.0".parse().unwrap(), "1.0.3.255".parse().unwrap())
+        );
+        assert_eq!(
+            parse_network("2001:200::/32").unwrap(),
+            (
+                "2001:200::".parse().unwrap(),
+                "2001:200:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap()
+            )
+        );
+        assert!(parse_network("1.0.0.0/33").is_err());
+        assert!(parse_network("1.0.0.0").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn geolite2_country() {
+        let db = DbIpDatabase::<CountryCode>::from_geolite2_csv_readers(
+            vec![BLOCKS_V4.as_bytes(), BLOCKS_V6.as_bytes()],
+            LOCATIONS.as_bytes(),
+        )
+        .unwrap();
+
+        let get = |ip: &str| db.get(&ip.parse().unwrap());
+        assert_eq!(get("1.0.0.7"), CountryCode::from_str("AU"));
+        assert_eq!(get("1.0.1.7"), CountryCode::from_str("US"));
+        assert_eq!(get("1.0.2.7"), None);
+        assert_eq!(get("1.0.4.7"), None);
+        assert_eq!(get("2001:200::1"), CountryCode::from_str("US"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn geolite2_continent() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        struct NorthAmerica;
+
+        impl crate::IpData for NorthAmerica {
+            fn from_ip_record(record: &crate::IpRecord<'_>) -> Option<Self> {
+                Some(NorthAmerica).filter(|_| record.continent_code == Some("NA"))
+            }
+        }
+
+        let db = DbIpDatabase::<NorthAmerica>::from_geolite2_csv_readers(
+            vec![BLOCKS_V4.as_bytes(), BLOCKS_V6.as_bytes()],
+            LOCATIONS.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(db.get(&"1.0.0.7".parse().unwrap()), None);
+        assert_eq!(db.get(&"1.0.1.7".parse().unwrap()), Some(NorthAmerica));
+    }
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn geolite2_csv_ip_data() {
+        /// Implements only `CsvIpData`, reading the country code of db-ip.com's country data.
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        struct Legacy(CountryCode);
+
+        impl crate::CsvIpData for Legacy {
+            fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
+                Ok(CountryCode::from_str(&record[2]).map(Self))
+            }
+        }
+
+        let db = DbIpDatabase::<Legacy>::from_geolite2_csv_readers(
+            vec![BLOCKS_V4.as_bytes(), BLOCKS_V6.as_bytes()],
+            LOCATIONS.as_bytes(),
+        )
+        .unwrap();
+
+        // Not the continent code, "NA", which is also the country code of Namibia.
+        let us = CountryCode::from_str("US").map(Legacy);
+        assert_eq!(db.get(&"1.0.1.7".parse().unwrap()), us);
+        assert_eq!(db.get(&"2001:200::1".parse().unwrap()), us);
+    }
+
+    #[test]
+    #[cfg(feature = "ipv4")]
+    fn unknown_geoname_id() {
+        let blocks = "network,geoname_id,registered_country_geoname_id\n\
+                      1.0.0.0/24,42,2077456\n\
+                      1.0.1.0/24,42,\n\
+                      1.0.2.0/24,6252001,\n";
+        let db = DbIpDatabase::<CountryCode>::from_geolite2_csv_readers(
+            vec![blocks.as_bytes()],
+            LOCATIONS.as_bytes(),
+        )
+        .unwrap();
+        let get = |ip: &str| db.get_v4(&ip.parse().unwrap());
+        assert_eq!(get("1.0.0.7"), CountryCode::from_str("AU"));
+        assert_eq!(get("1.0.1.7"), None);
+        assert_eq!(get("1.0.2.7"), CountryCode::from_str("US"));
+    }
+}
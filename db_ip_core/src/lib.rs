@@ -1,24 +1,90 @@
 #![forbid(unsafe_code)]
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
 
-use std::convert::TryInto;
-use std::fmt;
-use std::fmt::{Debug, Display, Formatter};
 #[allow(unused_imports)]
-use std::io::Read;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+#[allow(unused_imports)]
+use core::ops::RangeInclusive;
 #[allow(unused_imports)]
-use std::net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr};
+use core::str::FromStr;
 #[allow(unused_imports)]
-use std::str::FromStr;
+use net::{AddrParseError, IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+use std::io::Read;
+
+// `core::net` requires Rust 1.77, so it's only used without `std`.
+#[cfg(not(feature = "std"))]
+use core::net;
+#[cfg(feature = "std")]
+use std::net;
+
+#[cfg(all(feature = "bincode", any(feature = "ipv4", feature = "ipv6")))]
+mod bytes;
+#[cfg(all(feature = "export", any(feature = "ipv4", feature = "ipv6")))]
+mod cidr;
+#[cfg(all(feature = "compress", any(feature = "ipv4", feature = "ipv6")))]
+mod compress;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+mod database_ref;
+#[cfg(all(feature = "export", any(feature = "ipv4", feature = "ipv6")))]
+mod export;
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+mod from_csv;
+#[cfg(all(feature = "geolite2", any(feature = "ipv4", feature = "ipv6")))]
+mod geolite2;
+#[cfg(feature = "std")]
+pub mod lite;
+mod metadata;
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+mod parallel;
+mod record;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+mod restrict;
+#[cfg(all(feature = "rir", any(feature = "ipv4", feature = "ipv6")))]
+mod rir;
+#[cfg(all(feature = "updater", any(feature = "ipv4", feature = "ipv6")))]
+mod updater;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+mod validate;
+
+#[cfg(all(feature = "bincode", any(feature = "ipv4", feature = "ipv6")))]
+pub use bytes::FromBytesError;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+use database_ref::RangesRef;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+pub use database_ref::{static_starts_v4, static_starts_v6, DbIpDatabaseRef};
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+pub use from_csv::FromCsv;
+pub use metadata::{DatabaseMetadata, ATTRIBUTION};
+#[cfg(feature = "csv")]
+pub use record::CsvIpData;
+pub use record::IpRecord;
+#[cfg(all(feature = "updater", any(feature = "ipv4", feature = "ipv6")))]
+pub use updater::{Update, UpdateError, Updater};
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+pub use validate::{AddressFamily, InvalidDatabase};
 
 /// A map of ip range to data derived from a country code.
-#[derive(Debug)]
+///
+/// Deserialization fails if the data doesn't uphold the invariants checked by
+/// [`DbIpDatabase::validate`].
+#[derive(Clone, Debug)]
 #[cfg(any(feature = "ipv4", feature = "ipv6"))]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DbIpDatabase<V> {
     #[cfg(feature = "ipv4")]
     v4: DbIpDatabaseInner<u32, V>,
     #[cfg(feature = "ipv6")]
     v6: DbIpDatabaseInner<u128, V>,
+    /// Not serialized, since it describes how the database was built.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    metadata: DatabaseMetadata,
 }
 
 /// Errors that may arise when loading a [`DbIpDatabase`] from CSV.
@@ -43,7 +109,30 @@ pub enum FromCsvError {
 /// In general, the fewer the possibilities, the more compressed the data structure will be. For
 /// Example, if you mapped a country code to a boolean, the data structure will store very large
 /// ranges of true/false, consisting of multiple consecutive ranges in the original dataset.
+///
+/// If you serialize databases of your own type with `DbIpDatabase::to_bytes`, override
+/// [`IpData::TYPE_ID`]. Otherwise, all such types share the default of 0, so
+/// `DbIpDatabase::from_bytes` can't tell them apart.
 pub trait IpData: Copy + Clone + PartialEq {
+    /// Distinguishes serialized databases of different value types (see
+    /// `DbIpDatabase::to_bytes`).
+    ///
+    /// # Notes
+    ///
+    /// - The default, 0, is shared by every type that doesn't override it, so a database of one
+    ///   such type deserializes as another if their serialized values happen to be compatible.
+    /// - [`CountryCode`] uses 1 and `Region` uses 2. Pick another unique value for your type.
+    const TYPE_ID: u32 = 0;
+
+    /// Derives a value from a record of any source, such as db-ip.com's CSV data, GeoLite2 or the
+    /// Regional Internet Registries. Should return `None` if the data is irrelevant or missing.
+    ///
+    /// # Notes
+    ///
+    /// - Types written against the columns of db-ip.com's CSV data may implement [`CsvIpData`]
+    ///   instead, which implements this.
+    fn from_ip_record(record: &IpRecord<'_>) -> Option<Self>;
+
     /// db-ip data consists of csv records, any data must be derived from then.
     /// Should return [`Err(Error::InvalidRecord)`] if the fields are insufficient and the loading should
     /// be aborted, and [`Ok(None)`] if the field is fine, but the data is irrelevant.
@@ -51,10 +140,13 @@ pub trait IpData: Copy + Clone + PartialEq {
     /// # Notes
     ///
     /// - The first two indices are the begin and end of the ip range, respectively.
-    /// - You don't have to implement this if you disable the `csv` feature.
+    /// - By default, this parses the record with [`IpRecord::from_csv`] for
+    ///   [`IpData::from_ip_record`], which works with any source, so it needn't be implemented.
     /// - If you do implement it, you are responsible for knowing which indices correspond to which data.
     #[cfg(feature = "csv")]
-    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError>;
+    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
+        Ok(Self::from_ip_record(&IpRecord::from_csv(record)?))
+    }
 }
 
 /// A two letter, uppercase country code.
@@ -71,11 +163,28 @@ impl CountryCode {
     /// Returns an equivalent string e.g. `"US"` or `"AU"`.
     pub fn as_str(&self) -> &str {
         // We only ever put valid Utf8 bytes in.
-        std::str::from_utf8(&self.0).unwrap()
+        core::str::from_utf8(&self.0).unwrap()
+    }
+
+    /// Decodes country codes at compile time, for `static` data generated by a build step. Each
+    /// is two bytes, or two zero bytes if there is none.
+    #[doc(hidden)]
+    pub const fn static_values<const N: usize>(bytes: &[u8]) -> [Option<Self>; N] {
+        assert!(bytes.len() == N * 2);
+        let mut values = [None; N];
+        let mut i = 0;
+        while i < N {
+            let country_code = [bytes[i * 2], bytes[i * 2 + 1]];
+            if country_code[0] != 0 {
+                values[i] = Some(Self(country_code));
+            }
+            i += 1;
+        }
+        values
     }
 
     pub(crate) fn from_bytes(mut bytes: [u8; 2]) -> Option<Self> {
-        if std::str::from_utf8(&bytes).is_ok() {
+        if core::str::from_utf8(&bytes).is_ok() {
             for byte in &mut bytes {
                 *byte = byte.to_ascii_uppercase();
             }
@@ -100,20 +209,10 @@ impl Display for CountryCode {
 }
 
 impl IpData for CountryCode {
-    #[cfg(feature = "csv")]
-    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
-        let idx = match record.len() {
-            // Country data
-            3 => 2,
-            // City data
-            8 => 3,
-            // Not present.
-            _ => return Err(FromCsvError::InvalidRecord),
-        };
-        let country_code_str = record.get(idx).ok_or(FromCsvError::InvalidRecord)?;
-        let country_code =
-            CountryCode::from_str(country_code_str).ok_or(FromCsvError::InvalidRecord)?;
-        Ok(Some(country_code))
+    const TYPE_ID: u32 = 1;
+
+    fn from_ip_record(record: &IpRecord<'_>) -> Option<Self> {
+        record.country_code
     }
 }
 
@@ -131,36 +230,102 @@ pub enum Region {
     SouthAmerica,
 }
 
+#[cfg(feature = "region")]
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "region")]
+impl Region {
+    /// Returns the region of a country, if known.
+    pub fn from_country_code(country_code: CountryCode) -> Option<Self> {
+        db_ip_macros::country_code_str_to_region!(country_code.as_str())
+    }
+
+    /// Decodes regions at compile time, for `static` data generated by a build step. Each is a
+    /// byte, one more than the index of the variant, or zero if there is none.
+    #[doc(hidden)]
+    pub const fn static_values<const N: usize>(bytes: &[u8]) -> [Option<Self>; N] {
+        assert!(bytes.len() == N);
+        let mut values = [None; N];
+        let mut i = 0;
+        while i < N {
+            values[i] = match bytes[i] {
+                0 => None,
+                1 => Some(Self::Africa),
+                2 => Some(Self::Asia),
+                3 => Some(Self::Europe),
+                4 => Some(Self::NorthAmerica),
+                5 => Some(Self::Oceania),
+                6 => Some(Self::SouthAmerica),
+                _ => panic!("invalid region"),
+            };
+            i += 1;
+        }
+        values
+    }
+}
+
 #[cfg(feature = "region")]
 impl IpData for Region {
-    #[cfg(feature = "csv")]
-    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
-        let country_code = CountryCode::from_record(record)?;
-        Ok(country_code.and_then(|cc| db_ip_macros::country_code_str_to_region!(cc.as_str())))
+    const TYPE_ID: u32 = 2;
+
+    fn from_ip_record(record: &IpRecord<'_>) -> Option<Self> {
+        record.country_code.and_then(Self::from_country_code)
     }
 }
 
 #[cfg(any(feature = "ipv4", feature = "ipv6"))]
 impl<V: IpData> DbIpDatabase<V> {
+    /// Borrows the database as a [`DbIpDatabaseRef`].
+    pub fn as_database_ref(&self) -> DbIpDatabaseRef<'_, V> {
+        DbIpDatabaseRef {
+            #[cfg(feature = "ipv4")]
+            v4: self.v4.as_ranges_ref(),
+            #[cfg(feature = "ipv6")]
+            v6: self.v6.as_ranges_ref(),
+        }
+    }
+
     /// Gets the value associated with an ip address, if any.
     #[cfg(all(feature = "ipv4", feature = "ipv6"))]
     pub fn get(&self, ip: &IpAddr) -> Option<V> {
-        match ip {
-            IpAddr::V4(v4) => self.get_v4(v4),
-            IpAddr::V6(v6) => self.get_v6(v6),
-        }
+        self.as_database_ref().get(ip)
     }
 
     /// Gets the value associated with an Ipv4 address, if any.
     #[cfg(feature = "ipv4")]
     pub fn get_v4(&self, v4: &Ipv4Addr) -> Option<V> {
-        self.v4.lookup(&ip_v4_to_ne(v4))
+        self.as_database_ref().get_v4(v4)
     }
 
     /// Gets the value associated with an Ipv6 address, if any.
     #[cfg(feature = "ipv6")]
     pub fn get_v6(&self, v6: &Ipv6Addr) -> Option<V> {
-        self.v6.lookup(&ip_v6_to_ne(v6))
+        self.as_database_ref().get_v6(v6)
+    }
+
+    /// Gets the value associated with an ip address, if any, along with the address range it
+    /// applies to.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn get_range(&self, ip: &IpAddr) -> Option<(RangeInclusive<IpAddr>, V)> {
+        self.as_database_ref().get_range(ip)
+    }
+
+    /// Gets the value associated with an Ipv4 address, if any, along with the address range it
+    /// applies to.
+    #[cfg(feature = "ipv4")]
+    pub fn get_range_v4(&self, v4: &Ipv4Addr) -> Option<(RangeInclusive<Ipv4Addr>, V)> {
+        self.as_database_ref().get_range_v4(v4)
+    }
+
+    /// Gets the value associated with an Ipv6 address, if any, along with the address range it
+    /// applies to.
+    #[cfg(feature = "ipv6")]
+    pub fn get_range_v6(&self, v6: &Ipv6Addr) -> Option<(RangeInclusive<Ipv6Addr>, V)> {
+        self.as_database_ref().get_range_v6(v6)
     }
 
     /// Returns number of ranges/values stored for both Ipv4 and Ipv6 addresses.
@@ -172,91 +337,194 @@ impl<V: IpData> DbIpDatabase<V> {
     /// Returns number of ranges/values stored for Ipv4 addresses.
     #[cfg(feature = "ipv4")]
     pub fn len_v4(&self) -> usize {
-        self.v4.len()
+        self.as_database_ref().len_v4()
     }
 
     /// Returns number of ranges/values stored for Ipv6 addresses.
     #[cfg(feature = "ipv6")]
     pub fn len_v6(&self) -> usize {
-        self.v6.len()
+        self.as_database_ref().len_v6()
+    }
+
+    /// Returns whether no ranges/values are stored for either Ipv4 or Ipv6 addresses.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn is_empty(&self) -> bool {
+        self.as_database_ref().is_empty()
+    }
+
+    /// Returns whether no ranges/values are stored for Ipv4 addresses.
+    #[cfg(feature = "ipv4")]
+    pub fn is_empty_v4(&self) -> bool {
+        self.as_database_ref().is_empty_v4()
+    }
+
+    /// Returns whether no ranges/values are stored for Ipv6 addresses.
+    #[cfg(feature = "ipv6")]
+    pub fn is_empty_v6(&self) -> bool {
+        self.as_database_ref().is_empty_v6()
+    }
+
+    /// Returns where the data came from, such as the month of an embedded lite database, and the
+    /// number of ranges stored.
+    pub fn metadata(&self) -> DatabaseMetadata {
+        DatabaseMetadata {
+            #[cfg(feature = "ipv4")]
+            ranges_v4: self.len_v4(),
+            #[cfg(feature = "ipv6")]
+            ranges_v6: self.len_v6(),
+            ..self.metadata.clone()
+        }
+    }
+
+    /// Replaces the metadata, except for the number of ranges, which are always accurate.
+    pub fn with_metadata(mut self, metadata: DatabaseMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Iterates the address ranges that have a value, in ascending order, Ipv4 first.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn iter(&self) -> impl Iterator<Item = (RangeInclusive<IpAddr>, V)> + '_ {
+        self.as_database_ref().iter()
+    }
+
+    /// Like [`DbIpDatabase::iter`], but starts with the range containing `ip` (or the first one
+    /// after it), which is found by binary search.
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    pub fn iter_from(&self, ip: &IpAddr) -> impl Iterator<Item = (RangeInclusive<IpAddr>, V)> + '_ {
+        self.as_database_ref().iter_from(ip)
+    }
+
+    /// Iterates the Ipv4 address ranges that have a value, in ascending order.
+    #[cfg(feature = "ipv4")]
+    pub fn iter_v4(&self) -> impl Iterator<Item = (RangeInclusive<Ipv4Addr>, V)> + '_ {
+        self.as_database_ref().iter_v4()
+    }
+
+    /// Iterates the Ipv6 address ranges that have a value, in ascending order.
+    #[cfg(feature = "ipv6")]
+    pub fn iter_v6(&self) -> impl Iterator<Item = (RangeInclusive<Ipv6Addr>, V)> + '_ {
+        self.as_database_ref().iter_v6()
     }
 
     /// Load from CSV file contained in string.
     #[cfg(feature = "csv")]
     pub fn from_csv_str(csv: &str) -> Result<Self, FromCsvError> {
-        Self::from_csv_reader(csv.as_bytes())
+        <Self as FromCsv>::from_csv_str(csv)
     }
 
     /// Load from CSV file reader.
     #[cfg(feature = "csv")]
     pub fn from_csv_reader<R: Read>(reader: R) -> Result<Self, FromCsvError> {
-        let reader = csv::ReaderBuilder::new()
-            .has_headers(false)
-            .from_reader(reader);
-
-        Self::from_csv_reader_inner(reader)
+        <Self as FromCsv>::from_csv_reader(reader)
     }
 
     /// Load from CSV file contained in file.
+    ///
+    /// With the `gzip` feature, gzip-compressed files (such as the `.csv.gz` files served by
+    /// db-ip.com) are detected by their magic bytes and decompressed automatically.
     #[cfg(feature = "csv")]
     pub fn from_csv_file(path: &str) -> Result<Self, FromCsvError> {
-        let reader = csv::ReaderBuilder::new()
-            .has_headers(false)
-            .from_path(path)
-            .map_err(FromCsvError::Csv)?;
+        <Self as FromCsv>::from_csv_file(path)
+    }
 
-        Self::from_csv_reader_inner(reader)
+    /// Load from gzip-compressed CSV file reader.
+    #[cfg(feature = "gzip")]
+    pub fn from_csv_gz_reader<R: Read>(reader: R) -> Result<Self, FromCsvError> {
+        <Self as FromCsv>::from_csv_gz_reader(reader)
     }
 
-    #[cfg(feature = "csv")]
-    fn from_csv_reader_inner<R: Read>(mut reader: csv::Reader<R>) -> Result<Self, FromCsvError> {
-        #[cfg(feature = "ipv4")]
-        let mut v4 = DbIpDatabaseInnerBuilder::new();
-        #[cfg(feature = "ipv6")]
-        let mut v6 = DbIpDatabaseInnerBuilder::new();
-
-        for record in reader.records() {
-            let record = record.map_err(FromCsvError::Csv)?;
-
-            if let Some(value) = V::from_record(&record)? {
-                let begin = IpAddr::from_str(&record[0]).map_err(FromCsvError::AddrParse)?;
-                let end = IpAddr::from_str(&record[1]).map_err(FromCsvError::AddrParse)?;
-
-                if begin.is_ipv4() != end.is_ipv4() {
-                    return Err(FromCsvError::AddrMismatch);
-                }
-
-                match (begin, end) {
-                    #[cfg(feature = "ipv4")]
-                    (IpAddr::V4(begin), IpAddr::V4(end)) => {
-                        let begin_ne = ip_v4_to_ne(&begin);
-                        let end_ne = ip_v4_to_ne(&end);
-
-                        v4.push(begin_ne, end_ne, end_ne.checked_add(1), value)?;
-                    }
-                    #[cfg(feature = "ipv6")]
-                    (IpAddr::V6(begin), IpAddr::V6(end)) => {
-                        let begin_ne = ip_v6_to_ne(&begin);
-                        let end_ne = ip_v6_to_ne(&end);
-
-                        v6.push(begin_ne, end_ne, end_ne.checked_add(1), value)?;
-                    }
-                    _ => {}
-                }
+    /// Load from gzip-compressed CSV file, e.g. `dbip-country-lite-2022-02.csv.gz`.
+    #[cfg(feature = "gzip")]
+    pub fn from_csv_gz_file(path: &str) -> Result<Self, FromCsvError> {
+        <Self as FromCsv>::from_csv_gz_file(path)
+    }
+}
+
+/// Helps build [`DbIpDatabase`] from sorted address ranges, regardless of their source.
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+pub(crate) struct DbIpDatabaseBuilder<V> {
+    #[cfg(feature = "ipv4")]
+    v4: DbIpDatabaseInnerBuilder<u32, V>,
+    #[cfg(feature = "ipv6")]
+    v6: DbIpDatabaseInnerBuilder<u128, V>,
+}
+
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+impl<V: IpData> DbIpDatabaseBuilder<V> {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "ipv4")]
+            v4: DbIpDatabaseInnerBuilder::new(),
+            #[cfg(feature = "ipv6")]
+            v6: DbIpDatabaseInnerBuilder::new(),
+        }
+    }
+
+    /// Adds one inclusive IP range. Ranges of each address family must be pushed in ascending
+    /// order. Ranges of a disabled address family are ignored.
+    #[allow(unused_variables)]
+    pub fn push(&mut self, begin: IpAddr, end: IpAddr, value: V) -> Result<(), FromCsvError> {
+        match (begin, end) {
+            #[cfg(feature = "ipv4")]
+            (IpAddr::V4(begin), IpAddr::V4(end)) => {
+                let begin_ne = ip_v4_to_ne(&begin);
+                let end_ne = ip_v4_to_ne(&end);
+
+                self.v4.push(begin_ne, end_ne, end_ne.checked_add(1), value)
             }
+            #[cfg(feature = "ipv6")]
+            (IpAddr::V6(begin), IpAddr::V6(end)) => {
+                let begin_ne = ip_v6_to_ne(&begin);
+                let end_ne = ip_v6_to_ne(&end);
+
+                self.v6.push(begin_ne, end_ne, end_ne.checked_add(1), value)
+            }
+            (begin, end) if begin.is_ipv4() != end.is_ipv4() => Err(FromCsvError::AddrMismatch),
+            _ => Ok(()),
+        }
+    }
+
+    /// Builds the database, extending the last range of each address family to its last
+    /// address, as db-ip.com's data covers the whole address space.
+    pub fn build(self) -> DbIpDatabase<V> {
+        DbIpDatabase {
+            #[cfg(feature = "ipv4")]
+            v4: self.v4.build(),
+            #[cfg(feature = "ipv6")]
+            v6: self.v6.build(),
+            metadata: DatabaseMetadata::default(),
         }
+    }
 
-        Ok(Self {
+    /// Builds the database, ending the last range of each address family where the last pushed
+    /// range ends, for sources that don't cover the whole address space.
+    #[cfg(any(feature = "geolite2", feature = "rir"))]
+    pub fn build_bounded(self) -> DbIpDatabase<V> {
+        DbIpDatabase {
             #[cfg(feature = "ipv4")]
-            v4: v4.inner,
+            v4: self.v4.build_bounded(),
             #[cfg(feature = "ipv6")]
-            v6: v6.inner,
-        })
+            v6: self.v6.build_bounded(),
+            metadata: DatabaseMetadata::default(),
+        }
     }
 }
 
+/// Derives a value from a bare country code, for sources that have nothing but a country code
+/// to offer.
+#[cfg(all(feature = "rir", any(feature = "ipv4", feature = "ipv6")))]
+pub(crate) fn value_from_country_code<V: IpData>(
+    country_code: &str,
+) -> Result<Option<V>, FromCsvError> {
+    let country_code = CountryCode::from_str(country_code).ok_or(FromCsvError::InvalidRecord)?;
+    Ok(V::from_ip_record(&IpRecord::from_country_code(
+        country_code,
+    )))
+}
+
 /// Stores either Ipv4 or Ipv6 addresses/values.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct DbIpDatabaseInner<IP, V> {
     /// Sorted address range starts, in native endian.
@@ -266,6 +534,14 @@ struct DbIpDatabaseInner<IP, V> {
 }
 
 impl<IP: Ord + Copy, V: IpData> DbIpDatabaseInner<IP, V> {
+    #[cfg(any(feature = "ipv4", feature = "ipv6"))]
+    fn as_ranges_ref(&self) -> RangesRef<'_, IP, V> {
+        RangesRef {
+            starts: &self.starts,
+            values: &self.values,
+        }
+    }
+
     #[cfg(feature = "csv")]
     fn new() -> Self {
         Self {
@@ -273,27 +549,6 @@ impl<IP: Ord + Copy, V: IpData> DbIpDatabaseInner<IP, V> {
             values: Vec::new(),
         }
     }
-
-    /// Lookup value associated with native endian IP address, based on range.
-    fn lookup(&self, ip: &IP) -> Option<V> {
-        debug_assert_eq!(self.starts.len(), self.values.len());
-
-        match self.starts.binary_search(ip) {
-            Ok(idx) => self.values[idx],
-            Err(idx) => {
-                if idx > 0 && self.starts.get(idx).map(|end| ip < end).unwrap_or(true) {
-                    self.values.get(idx - 1).copied().unwrap_or(None)
-                } else {
-                    None
-                }
-            }
-        }
-    }
-
-    /// How many IP ranges.
-    fn len(&self) -> usize {
-        self.values.len()
-    }
 }
 
 /// Helps build [`DbIpDatabaseInner`] from sorted CSV data.
@@ -329,6 +584,11 @@ impl<IP: Ord + Copy + Default, V: IpData> DbIpDatabaseInnerBuilder<IP, V> {
         if start < self.next || start > end {
             return Err(FromCsvError::AddrOutOfOrder);
         }
+        if start > self.next {
+            // Gap of unknown values.
+            self.inner.starts.push(self.next);
+            self.inner.values.push(None);
+        }
         if self
             .inner
             .values
@@ -336,12 +596,6 @@ impl<IP: Ord + Copy + Default, V: IpData> DbIpDatabaseInnerBuilder<IP, V> {
             .map(|last| last != &Some(value))
             .unwrap_or(true)
         {
-            if start > self.next {
-                // Gap of unknown values.
-                self.inner.starts.push(self.next);
-                self.inner.values.push(None);
-                self.next = start;
-            }
             self.inner.starts.push(start);
             self.inner.values.push(Some(value));
         }
@@ -352,6 +606,21 @@ impl<IP: Ord + Copy + Default, V: IpData> DbIpDatabaseInnerBuilder<IP, V> {
         }
         Ok(())
     }
+
+    /// The last IP range extends to the last address.
+    pub fn build(self) -> DbIpDatabaseInner<IP, V> {
+        self.inner
+    }
+
+    /// Terminates the last IP range, if it doesn't extend to the last address.
+    #[cfg(any(feature = "geolite2", feature = "rir"))]
+    pub fn build_bounded(mut self) -> DbIpDatabaseInner<IP, V> {
+        if !self.done && matches!(self.inner.values.last(), Some(Some(_))) {
+            self.inner.starts.push(self.next);
+            self.inner.values.push(None);
+        }
+        self.inner
+    }
 }
 
 #[cfg(feature = "ipv4")]
@@ -486,6 +755,78 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(all(feature = "csv", feature = "ipv4"))]
+    fn gap_between_equal_values() {
+        let db = DbIpDatabase::<CountryCode>::from_csv_str(
+            "1.0.0.0,1.0.0.255,AU\n1.0.2.0,1.0.2.255,AU\n",
+        )
+        .unwrap();
+        assert_eq!(
+            db.get_v4(&"1.0.0.1".parse().unwrap()),
+            CountryCode::from_str("AU")
+        );
+        assert_eq!(db.get_v4(&"1.0.1.1".parse().unwrap()), None);
+        assert_eq!(
+            db.get_v4(&"1.0.2.1".parse().unwrap()),
+            CountryCode::from_str("AU")
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "csv", feature = "ipv4", feature = "ipv6"))]
+    fn get_range() {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv"))
+                .unwrap();
+        assert_eq!(
+            db.get_range(&"1.2.3.4".parse().unwrap()),
+            Some((
+                "1.0.0.0".parse().unwrap()..="255.255.255.255".parse().unwrap(),
+                CountryCode::from_str("AU").unwrap()
+            ))
+        );
+        assert_eq!(db.get_range(&"0.0.0.0".parse().unwrap()), None);
+        assert_eq!(db.get_range(&"::1".parse().unwrap()), None);
+        assert_eq!(
+            db.get_range_v6(&"2a07:7ec5:8233::1".parse().unwrap()),
+            Some((
+                "2a07:7ec5:8233::".parse().unwrap()
+                    ..="ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap(),
+                CountryCode::from_str("AU").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "gzip", feature = "ipv4", feature = "ipv6"))]
+    fn from_csv_gz() {
+        use std::io::Write;
+
+        let csv = include_str!("../../test_country_data.csv");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(csv.as_bytes()).unwrap();
+        let gz = encoder.finish().unwrap();
+
+        let db = DbIpDatabase::<CountryCode>::from_csv_gz_reader(gz.as_slice()).unwrap();
+        assert_eq!(
+            db.len(),
+            DbIpDatabase::<CountryCode>::from_csv_str(csv)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            db.get(&"1.2.3.4".parse().unwrap()),
+            Some(CountryCode::from_str("AU").unwrap())
+        );
+
+        let path = std::env::temp_dir().join(format!("db_ip_test_{}.csv.gz", std::process::id()));
+        std::fs::write(&path, &gz).unwrap();
+        let detected = DbIpDatabase::<CountryCode>::from_csv_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(detected.unwrap().len(), db.len());
+    }
+
     #[test]
     #[cfg(feature = "ipv4")]
     fn compare_v4() {
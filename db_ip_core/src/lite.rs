@@ -0,0 +1,179 @@
+//! Locating the monthly "lite" databases published by db-ip.com.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where db-ip.com publishes the lite databases.
+pub const LITE_BASE_URL: &str = "https://download.db-ip.com/free";
+
+/// The month that lite databases are looked for in if none of the recent months are available.
+pub const LITE_FALLBACK_MONTH: (i32, u32) = (2022, 2);
+
+/// How many months, including the current one, are looked for before falling back to
+/// [`LITE_FALLBACK_MONTH`].
+pub const LITE_MONTHS: usize = 48;
+
+/// The URL of the lite database of an edition (e.g. `country` or `city`) for a month, e.g.
+/// `https://download.db-ip.com/free/dbip-country-lite-2022-02.csv.gz`.
+pub fn lite_url(base_url: &str, edition: &str, (year, month): (i32, u32)) -> String {
+    format!(
+        "{}/dbip-{}-lite-{}-{:02}.csv.gz",
+        base_url.trim_end_matches('/'),
+        edition,
+        year,
+        month
+    )
+}
+
+/// The months, as `(year, month)`, that lite databases are looked for in, most recent first: the
+/// month of `now`, the preceding months, and finally [`LITE_FALLBACK_MONTH`].
+pub fn lite_months(now: SystemTime) -> impl Iterator<Item = (i32, u32)> {
+    let (mut year, mut month) = year_month(now);
+    std::iter::from_fn(move || {
+        let ret = (year, month);
+        if month == 1 {
+            year -= 1;
+            month = 12;
+        } else {
+            month -= 1;
+        }
+        Some(ret)
+    })
+    .take(LITE_MONTHS)
+    .chain(std::iter::once(LITE_FALLBACK_MONTH))
+}
+
+/// Parses a `YYYY-MM` month, e.g. `2022-02`, into `(year, month)`.
+pub fn parse_month(s: &str) -> Option<(i32, u32)> {
+    let (year, month) = s.trim().split_once('-')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    let year = year.parse().ok()?;
+    let month = month.parse().ok().filter(|m| (1..=12).contains(m))?;
+    Some((year, month))
+}
+
+/// When a lite database is built, as recorded in its metadata: `source_date_epoch` (the value of
+/// `SOURCE_DATE_EPOCH`) if set, and otherwise the start of the `pinned` month if the data was
+/// pinned, so that a pinned build is reproducible. Only unpinned builds record `now`.
+pub fn build_time(
+    source_date_epoch: Option<&str>,
+    pinned: Option<(i32, u32)>,
+    now: SystemTime,
+) -> SystemTime {
+    source_date_epoch
+        .and_then(|secs| secs.trim().parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        .or_else(|| pinned.map(month_start))
+        .unwrap_or(now)
+}
+
+/// The instant a month starts (UTC).
+pub fn month_start((year, month): (i32, u32)) -> SystemTime {
+    // Days from civil, http://howardhinnant.github.io/date_algorithms.html
+    let y = (year - (month <= 2) as i32) as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    if days >= 0 {
+        UNIX_EPOCH + Duration::from_secs(days as u64 * 86400)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(days.unsigned_abs() * 86400)
+    }
+}
+
+/// The `(year, month)` (UTC) of an instant.
+pub fn year_month(time: SystemTime) -> (i32, u32) {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = secs.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+    (year, month)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        build_time, lite_months, lite_url, month_start, parse_month, year_month, LITE_BASE_URL,
+    };
+    use crate::DatabaseMetadata;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn months() {
+        for &(year, month, secs) in &[
+            (1970, 1, 0),
+            (2000, 3, 951868800),
+            (2022, 2, 1643673600),
+            (2024, 12, 1733011200),
+        ] {
+            assert_eq!(
+                month_start((year, month)),
+                UNIX_EPOCH + Duration::from_secs(secs)
+            );
+            assert_eq!(year_month(month_start((year, month))), (year, month));
+            assert_eq!(
+                year_month(month_start((year, month)) - Duration::from_secs(1)),
+                if month == 1 {
+                    (year - 1, 12)
+                } else {
+                    (year, month - 1)
+                }
+            );
+        }
+
+        let months = lite_months(month_start((2024, 2))).collect::<Vec<_>>();
+        assert_eq!(months.len(), 49);
+        assert_eq!(months[..3], [(2024, 2), (2024, 1), (2023, 12)]);
+        assert_eq!(months[47], (2020, 3));
+        assert_eq!(months[48], (2022, 2));
+
+        assert_eq!(parse_month("2022-02"), Some((2022, 2)));
+        assert_eq!(parse_month("2022-13"), None);
+        assert_eq!(parse_month("2022-2"), None);
+        assert_eq!(parse_month("22-02"), None);
+    }
+
+    #[test]
+    fn url() {
+        assert_eq!(
+            lite_url(LITE_BASE_URL, "country", (2022, 2)),
+            "https://download.db-ip.com/free/dbip-country-lite-2022-02.csv.gz"
+        );
+        assert_eq!(
+            lite_url("http://localhost:8080/", "city", (2023, 11)),
+            "http://localhost:8080/dbip-city-lite-2023-11.csv.gz"
+        );
+    }
+
+    #[test]
+    fn pinned_build() {
+        // Two builds of the same pinned month, an hour apart.
+        let metadata = |now: SystemTime, source_date_epoch: Option<&str>| {
+            let built_at = build_time(source_date_epoch, Some((2024, 5)), now);
+            let built_at = built_at.duration_since(UNIX_EPOCH).unwrap().as_secs();
+            DatabaseMetadata::lite("", 2024, 5, built_at)
+        };
+        let first = metadata(SystemTime::now(), None);
+        let second = metadata(SystemTime::now() + Duration::from_secs(3600), None);
+        assert_eq!(first, second);
+        assert_eq!(first.built_at, Some(1714521600));
+        assert_eq!(metadata(SystemTime::now(), Some("42")).built_at, Some(42));
+
+        let now = SystemTime::now();
+        assert_eq!(build_time(None, None, now), now);
+        assert_eq!(build_time(Some("garbage"), None, now), now);
+    }
+}
@@ -0,0 +1,80 @@
+//! Information about where the data in a database came from.
+
+use alloc::borrow::Cow;
+
+/// The attribution that db-ip.com requires wherever its lite databases are used, under the
+/// terms of the [Creative Commons Attribution 4.0 International License](https://creativecommons.org/licenses/by/4.0/).
+pub const ATTRIBUTION: &str = "IP Geolocation by DB-IP (https://db-ip.com)";
+
+/// Information about where the data in a database came from, as returned by
+/// [`DbIpDatabase::metadata`](crate::DbIpDatabase::metadata).
+///
+/// Databases loaded from CSV have no source information, unless it is set with
+/// [`DbIpDatabase::with_metadata`](crate::DbIpDatabase::with_metadata).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DatabaseMetadata {
+    /// Where the data was downloaded from.
+    pub source_url: Option<Cow<'static, str>>,
+    /// The `(year, month)` of the data.
+    pub data_month: Option<(i32, u32)>,
+    /// When the database was built, in seconds since the Unix epoch.
+    pub built_at: Option<u64>,
+    /// Text that must be displayed wherever the data is used, such as [`ATTRIBUTION`].
+    pub attribution: Option<Cow<'static, str>>,
+    /// The number of Ipv4 ranges, including those without a value.
+    pub ranges_v4: usize,
+    /// The number of Ipv6 ranges, including those without a value.
+    pub ranges_v6: usize,
+}
+
+impl DatabaseMetadata {
+    /// Metadata for a db-ip.com lite database, which requires [`ATTRIBUTION`].
+    pub const fn lite(source_url: &'static str, year: i32, month: u32, built_at: u64) -> Self {
+        Self {
+            source_url: Some(Cow::Borrowed(source_url)),
+            data_month: Some((year, month)),
+            built_at: Some(built_at),
+            attribution: Some(Cow::Borrowed(ATTRIBUTION)),
+            ranges_v4: 0,
+            ranges_v6: 0,
+        }
+    }
+
+    /// How many whole months the data is older than `now`, e.g. `1` for data from January in
+    /// February. [`None`] if the data month is unknown.
+    #[cfg(feature = "std")]
+    pub fn months_old(&self, now: std::time::SystemTime) -> Option<u32> {
+        let (year, month) = self.data_month?;
+        let (now_year, now_month) = crate::lite::year_month(now);
+        let months = (now_year - year) * 12 + now_month as i32 - month as i32;
+        Some(months.max(0) as u32)
+    }
+
+    /// Whether the data is more than `months` months old, or of unknown age.
+    #[cfg(feature = "std")]
+    pub fn is_older_than(&self, months: u32, now: std::time::SystemTime) -> bool {
+        !matches!(self.months_old(now), Some(old) if old <= months)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod test {
+    use super::{DatabaseMetadata, ATTRIBUTION};
+    use crate::lite::month_start;
+
+    #[test]
+    fn age() {
+        let metadata = DatabaseMetadata::lite("https://example.com", 2023, 11, 0);
+        assert_eq!(metadata.attribution.as_deref(), Some(ATTRIBUTION));
+
+        let now = month_start((2024, 2));
+        assert_eq!(metadata.months_old(now), Some(3));
+        assert!(metadata.is_older_than(2, now));
+        assert!(!metadata.is_older_than(3, now));
+        assert_eq!(metadata.months_old(month_start((2020, 1))), Some(0));
+
+        assert_eq!(DatabaseMetadata::default().months_old(now), None);
+        assert!(DatabaseMetadata::default().is_older_than(3, now));
+    }
+}
@@ -0,0 +1,318 @@
+#[cfg(feature = "gzip")]
+use crate::from_csv::GZIP_MAGIC;
+use crate::{DbIpDatabase, DbIpDatabaseBuilder, FromCsvError, IpData};
+use alloc::vec::Vec;
+use core::str::FromStr;
+use std::net::IpAddr;
+
+/// Chunks smaller than this aren't worth a thread.
+const MIN_CHUNK_LEN: usize = 1 << 20;
+
+impl<V: IpData + Send> DbIpDatabase<V> {
+    /// Like [`DbIpDatabase::from_csv_str`], but faster, especially for large files such as
+    /// city data. The data is split into chunks on line boundaries, whose records are parsed on
+    /// separate threads, and then combined.
+    ///
+    /// Unlike db-ip.com's data, records must not contain line breaks in quoted fields.
+    pub fn from_csv_bytes_parallel(csv: &[u8]) -> Result<Self, FromCsvError> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::from_csv_chunks(csv, threads.min(csv.len() / MIN_CHUNK_LEN).max(1))
+    }
+
+    /// Like [`DbIpDatabase::from_csv_file`], but reads the whole file and loads it with
+    /// [`DbIpDatabase::from_csv_bytes_parallel`].
+    pub fn from_csv_file_parallel(path: &str) -> Result<Self, FromCsvError> {
+        let csv = std::fs::read(path).map_err(|e| FromCsvError::Csv(e.into()))?;
+        #[cfg(feature = "gzip")]
+        if csv.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            std::io::Read::read_to_end(
+                &mut flate2::read::MultiGzDecoder::new(csv.as_slice()),
+                &mut decompressed,
+            )
+            .map_err(|e| FromCsvError::Csv(e.into()))?;
+            return Self::from_csv_bytes_parallel(&decompressed);
+        }
+        Self::from_csv_bytes_parallel(&csv)
+    }
+
+    fn from_csv_chunks(csv: &[u8], chunks: usize) -> Result<Self, FromCsvError> {
+        let chunks = split_lines(csv, chunks);
+        let parsed: Vec<Result<Chunk<V>, FromCsvError>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = chunks
+                .iter()
+                .map(|chunk| scope.spawn(move || Chunk::parse(chunk)))
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+
+        // The usual merge rules and ordering checks apply across chunk boundaries.
+        let mut builder = DbIpDatabaseBuilder::new();
+        for chunk in parsed {
+            let chunk = chunk?;
+            #[cfg(feature = "ipv4")]
+            for (start, end, value) in chunk.v4 {
+                builder.v4.push(start, end, end.checked_add(1), value)?;
+            }
+            #[cfg(feature = "ipv6")]
+            for (start, end, value) in chunk.v6 {
+                builder.v6.push(start, end, end.checked_add(1), value)?;
+            }
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Splits into about `n` chunks, each ending with a line break (except maybe the last).
+fn split_lines(csv: &[u8], n: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::with_capacity(n);
+    let mut rest = csv;
+    for i in (1..n).rev() {
+        let target = rest.len() / (i + 1);
+        match rest[target..].iter().position(|&b| b == b'\n') {
+            Some(newline) => {
+                let (chunk, remainder) = rest.split_at(target + newline + 1);
+                chunks.push(chunk);
+                rest = remainder;
+            }
+            None => break,
+        }
+    }
+    chunks.push(rest);
+    chunks
+}
+
+/// Native endian address ranges of a chunk, in their original order, with consecutive ranges
+/// with the same value combined.
+struct Chunk<V> {
+    #[cfg(feature = "ipv4")]
+    v4: Vec<(u32, u32, V)>,
+    #[cfg(feature = "ipv6")]
+    v6: Vec<(u128, u128, V)>,
+}
+
+impl<V: IpData> Chunk<V> {
+    fn parse(csv: &[u8]) -> Result<Self, FromCsvError> {
+        let mut chunk = Self {
+            #[cfg(feature = "ipv4")]
+            v4: Vec::new(),
+            #[cfg(feature = "ipv6")]
+            v6: Vec::new(),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv);
+        // Reused, so that parsing a record doesn't allocate.
+        let mut record = csv::StringRecord::new();
+        while reader.read_record(&mut record).map_err(FromCsvError::Csv)? {
+            let value = match V::from_record(&record)? {
+                Some(value) => value,
+                None => continue,
+            };
+            let begin = parse_ip(record.get(0).ok_or(FromCsvError::InvalidRecord)?)?;
+            let end = parse_ip(record.get(1).ok_or(FromCsvError::InvalidRecord)?)?;
+            match (begin, end) {
+                #[cfg(feature = "ipv4")]
+                (IpAddr::V4(begin), IpAddr::V4(end)) => {
+                    combine(&mut chunk.v4, begin.into(), end.into(), value)
+                }
+                #[cfg(feature = "ipv6")]
+                (IpAddr::V6(begin), IpAddr::V6(end)) => {
+                    combine(&mut chunk.v6, begin.into(), end.into(), value)
+                }
+                (begin, end) if begin.is_ipv4() != end.is_ipv4() => {
+                    return Err(FromCsvError::AddrMismatch)
+                }
+                _ => {}
+            }
+        }
+        Ok(chunk)
+    }
+}
+
+/// Appends a range, or extends the last one if it is adjacent and has the same value.
+fn combine<IP: Copy + Ord + From<u8> + core::ops::Sub<Output = IP>, V: PartialEq>(
+    ranges: &mut Vec<(IP, IP, V)>,
+    start: IP,
+    end: IP,
+    value: V,
+) {
+    if let Some(last) = ranges.last_mut() {
+        let adjacent = start > last.1 && start - IP::from(1) == last.1 && end >= start;
+        if adjacent && last.2 == value {
+            last.1 = end;
+            return;
+        }
+    }
+    ranges.push((start, end, value));
+}
+
+/// Parses the common forms of addresses without going through [`IpAddr::from_str`], which is
+/// only used for other forms (and errors).
+fn parse_ip(s: &str) -> Result<IpAddr, FromCsvError> {
+    let bytes = s.as_bytes();
+    let fast = if bytes.contains(&b':') {
+        parse_ipv6(bytes).map(|ip| IpAddr::V6(ip.into()))
+    } else {
+        parse_ipv4(bytes).map(|ip| IpAddr::V4(ip.into()))
+    };
+    match fast {
+        Some(ip) => Ok(ip),
+        None => IpAddr::from_str(s).map_err(FromCsvError::AddrParse),
+    }
+}
+
+/// Parses four dotted decimal octets, without leading zeros.
+fn parse_ipv4(bytes: &[u8]) -> Option<u32> {
+    let mut ip = 0u32;
+    let mut octets = 0;
+    for octet in bytes.split(|&b| b == b'.') {
+        if octets == 4
+            || octet.is_empty()
+            || octet.len() > 3
+            || (octet.len() > 1 && octet[0] == b'0')
+        {
+            return None;
+        }
+        let mut value = 0u32;
+        for &b in octet {
+            if !b.is_ascii_digit() {
+                return None;
+            }
+            value = value * 10 + (b - b'0') as u32;
+        }
+        if value > 255 {
+            return None;
+        }
+        ip = ip << 8 | value;
+        octets += 1;
+    }
+    Some(ip).filter(|_| octets == 4)
+}
+
+/// Parses hexadecimal groups, with at most one `::`, but no embedded Ipv4 address.
+fn parse_ipv6(bytes: &[u8]) -> Option<u128> {
+    fn groups(bytes: &[u8], ip: &mut u128) -> Option<u32> {
+        if bytes.is_empty() {
+            return Some(0);
+        }
+        let mut count = 0;
+        for group in bytes.split(|&b| b == b':') {
+            if group.is_empty() || group.len() > 4 {
+                return None;
+            }
+            let mut value = 0u128;
+            for &b in group {
+                value = value << 4 | (b as char).to_digit(16)? as u128;
+            }
+            *ip = *ip << 16 | value;
+            count += 1;
+        }
+        Some(count)
+    }
+
+    let gap = bytes.windows(2).position(|w| w == b"::");
+    let mut head = 0u128;
+    match gap {
+        Some(gap) => {
+            let head_groups = groups(&bytes[..gap], &mut head)?;
+            let mut tail = 0u128;
+            let tail_groups = groups(&bytes[gap + 2..], &mut tail)?;
+            if head_groups + tail_groups > 7 {
+                return None;
+            }
+            let head_shift = 16 * (8 - head_groups);
+            Some(head.checked_shl(head_shift).unwrap_or(0) | tail)
+        }
+        None => {
+            if groups(bytes, &mut head)? != 8 {
+                return None;
+            }
+            Some(head)
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use super::{parse_ipv4, parse_ipv6, split_lines};
+    use crate::{CountryCode, DbIpDatabase, FromCsvError};
+    use std::fmt::Write;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn parse() {
+        for s in [
+            "0.0.0.0",
+            "1.2.3.4",
+            "255.255.255.255",
+            "256.0.0.0",
+            "01.0.0.0",
+            "1.2.3",
+            "1.2.3.4.5",
+            "1..3.4",
+            "1.2.3.a",
+            "",
+        ] {
+            assert_eq!(
+                parse_ipv4(s.as_bytes()),
+                s.parse::<Ipv4Addr>().ok().map(u32::from)
+            );
+        }
+        for s in [
+            "::",
+            "::1",
+            "1::",
+            "2a07:7ec5:8225::",
+            "2a07:7ec5:8232:ffff:ffff:ffff:ffff:ffff",
+            "1:2:3:4:5:6:7:8",
+            "1:2:3:4:5:6:7",
+            "1:2:3:4:5:6:7:8:9",
+            "1::2::3",
+            ":1::",
+            "1:::2",
+            "12345::",
+            "g::",
+            "1:2:3:4:5:6:7::",
+            "::1:2:3:4:5:6:7",
+        ] {
+            let parsed = parse_ipv6(s.as_bytes());
+            assert!(
+                parsed.is_none() || parsed == s.parse::<Ipv6Addr>().ok().map(u128::from),
+                "{}",
+                s
+            );
+        }
+        assert_eq!(parse_ipv6(b"1::"), Some(1 << 112));
+        assert_eq!(parse_ipv6(b"::1:2"), Some(1 << 16 | 2));
+    }
+
+    #[test]
+    fn chunks() {
+        let mut csv = String::from(include_str!("../../test_country_data.csv"));
+        csv.push('\n');
+        for i in 0..1000u32 {
+            let cc = ["US", "AU", "AU", "DE"][(i % 7 % 4) as usize];
+            let start = Ipv4Addr::from(2 << 24 | i << 8);
+            let end = Ipv4Addr::from(2 << 24 | i << 8 | 0xff);
+            writeln!(csv, "{},{},{}", start, end, cc).unwrap();
+        }
+        let expected = DbIpDatabase::<CountryCode>::from_csv_str(&csv).unwrap();
+        for n in [1, 2, 3, 17, 2000] {
+            assert!(split_lines(csv.as_bytes(), n).concat() == csv.as_bytes());
+            let db = DbIpDatabase::<CountryCode>::from_csv_chunks(csv.as_bytes(), n).unwrap();
+            assert!(db.iter().eq(expected.iter()), "{}", n);
+            assert_eq!(db.len(), expected.len());
+        }
+
+        let out_of_order = "1.0.0.0,1.0.0.255,US\n0.0.0.0,0.0.0.255,US\n";
+        assert!(matches!(
+            DbIpDatabase::<CountryCode>::from_csv_chunks(out_of_order.as_bytes(), 2),
+            Err(FromCsvError::AddrOutOfOrder)
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::CountryCode;
+#[cfg(feature = "csv")]
+use crate::{FromCsvError, IpData};
+#[cfg(feature = "csv")]
+use alloc::vec;
+
+/// The fields of a record, independent of the format of the source, so that
+/// [`IpData::from_ip_record`](crate::IpData::from_ip_record) works with any of them. Fields the
+/// source doesn't provide are `None`.
+///
+/// | Source | Fields |
+/// |--------|--------|
+/// | db-ip.com country data | `country_code` |
+/// | db-ip.com city data | `continent_code`, `country_code`, `subdivision`, `city`, `latitude`, `longitude` |
+/// | GeoLite2 | `continent_code`, `country_code`, and with city data, `subdivision` and `city` |
+/// | Regional Internet Registries | `country_code` |
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct IpRecord<'a> {
+    /// Two letter continent code, e.g. `"NA"`.
+    pub continent_code: Option<&'a str>,
+    pub country_code: Option<CountryCode>,
+    /// Name of the state, province, etc.
+    pub subdivision: Option<&'a str>,
+    pub city: Option<&'a str>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Autonomous system number.
+    pub asn: Option<u32>,
+    /// Autonomous system organization.
+    pub as_organization: Option<&'a str>,
+}
+
+impl<'a> IpRecord<'a> {
+    /// A record with nothing but a country code.
+    pub fn from_country_code(country_code: CountryCode) -> Self {
+        Self {
+            country_code: Some(country_code),
+            ..Self::default()
+        }
+    }
+
+    /// Parses a record of db-ip.com's country or city data, which must have 3 or 8 fields,
+    /// respectively. Empty fields are `None`.
+    #[cfg(feature = "csv")]
+    pub fn from_csv(record: &'a csv::StringRecord) -> Result<Self, FromCsvError> {
+        let field = |idx: usize| record.get(idx).filter(|field| !field.is_empty());
+        let country_code = |idx: usize| {
+            field(idx)
+                .map(|cc| CountryCode::from_str(cc).ok_or(FromCsvError::InvalidRecord))
+                .transpose()
+        };
+        match record.len() {
+            3 => Ok(Self {
+                country_code: country_code(2)?,
+                ..Self::default()
+            }),
+            8 => Ok(Self {
+                continent_code: field(2),
+                country_code: country_code(3)?,
+                subdivision: field(4),
+                city: field(5),
+                latitude: field(6).and_then(|latitude| latitude.parse().ok()),
+                longitude: field(7).and_then(|longitude| longitude.parse().ok()),
+                ..Self::default()
+            }),
+            _ => Err(FromCsvError::InvalidRecord),
+        }
+    }
+
+    /// The record in the layout of db-ip.com's country data, with an empty address range, for
+    /// [`CsvIpData`]. Everything but the country code is dropped, since types written against
+    /// that layout would misread the others, e.g. the continent code of the city data layout.
+    #[cfg(feature = "csv")]
+    pub(crate) fn to_country_csv(self) -> csv::StringRecord {
+        let country_code = self.country_code.as_ref().map_or("", CountryCode::as_str);
+        csv::StringRecord::from(vec!["", "", country_code])
+    }
+}
+
+/// Data derived from the columns of db-ip.com's CSV data, like [`IpData::from_record`], for types
+/// written before [`IpRecord`]. Implements [`IpData`], which other sources use, by passing them
+/// a record in the layout of db-ip.com's country data, with nothing but the country code.
+#[cfg(feature = "csv")]
+pub trait CsvIpData: Copy + Clone + PartialEq {
+    /// See [`IpData::TYPE_ID`].
+    const TYPE_ID: u32 = 0;
+
+    /// See [`IpData::from_record`].
+    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError>;
+}
+
+#[cfg(feature = "csv")]
+impl<T: CsvIpData> IpData for T {
+    const TYPE_ID: u32 = <T as CsvIpData>::TYPE_ID;
+
+    fn from_ip_record(record: &IpRecord<'_>) -> Option<Self> {
+        <T as CsvIpData>::from_record(&record.to_country_csv())
+            .ok()
+            .flatten()
+    }
+
+    fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
+        <T as CsvIpData>::from_record(record)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod test {
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    use super::CsvIpData;
+    use super::IpRecord;
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    use crate::IpData;
+    use crate::{CountryCode, FromCsvError};
+
+    #[test]
+    fn csv() {
+        let country = csv::StringRecord::from(vec!["1.0.0.0", "1.0.0.255", "AU"]);
+        let record = IpRecord::from_csv(&country).unwrap();
+        assert_eq!(
+            record,
+            IpRecord::from_country_code(CountryCode::from_str("AU").unwrap())
+        );
+        assert_eq!(record.to_country_csv().iter().nth(2), Some("AU"));
+
+        let city = csv::StringRecord::from(vec![
+            "1.0.0.0",
+            "1.0.0.255",
+            "OC",
+            "AU",
+            "Queensland",
+            "South Brisbane",
+            "-27.4767",
+            "153.017",
+        ]);
+        let record = IpRecord::from_csv(&city).unwrap();
+        assert_eq!(record.city, Some("South Brisbane"));
+        assert_eq!(record.latitude, Some(-27.4767));
+        assert_eq!(
+            record.to_country_csv().iter().collect::<Vec<_>>(),
+            ["", "", "AU"]
+        );
+
+        let invalid = csv::StringRecord::from(vec!["1.0.0.0", "1.0.0.255", "AUS"]);
+        assert!(matches!(
+            IpRecord::from_csv(&invalid),
+            Err(FromCsvError::InvalidRecord)
+        ));
+    }
+
+    /// Implements only [`IpData::from_ip_record`].
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Southern(bool);
+
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    impl IpData for Southern {
+        fn from_ip_record(record: &IpRecord<'_>) -> Option<Self> {
+            match (record.latitude, record.country_code) {
+                (Some(latitude), _) => Some(Self(latitude < 0.0)),
+                (None, Some(cc)) => Some(Self(["AU", "NZ"].contains(&cc.as_str()))),
+                _ => None,
+            }
+        }
+    }
+
+    /// Implements only [`CsvIpData`], like types written before [`IpRecord`].
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct Legacy(CountryCode);
+
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    impl CsvIpData for Legacy {
+        fn from_record(record: &csv::StringRecord) -> Result<Option<Self>, FromCsvError> {
+            Ok(CountryCode::from_str(&record[2]).map(Self))
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn defaults() {
+        let db = crate::DbIpDatabase::<Southern>::from_csv_str(include_str!(
+            "../../test_country_data.csv"
+        ))
+        .unwrap();
+        assert_eq!(db.get(&"1.0.0.0".parse().unwrap()), Some(Southern(true)));
+
+        let au = CountryCode::from_str("AU").unwrap();
+        let record = IpRecord {
+            latitude: Some(-27.4767),
+            ..IpRecord::from_country_code(au)
+        };
+        assert_eq!(Southern::from_ip_record(&record), Some(Southern(true)));
+        assert_eq!(
+            Legacy::from_ip_record(&IpRecord::from_country_code(au)),
+            Some(Legacy(au))
+        );
+        let city = IpRecord {
+            continent_code: Some("OC"),
+            ..record
+        };
+        assert_eq!(Legacy::from_ip_record(&city), Some(Legacy(au)));
+    }
+}
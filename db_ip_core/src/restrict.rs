@@ -0,0 +1,116 @@
+use crate::{AddressFamily, DbIpDatabase, DbIpDatabaseInner, IpData};
+use alloc::vec::Vec;
+
+impl<V: IpData> DbIpDatabase<V> {
+    /// Replaces each value with `f(value)`, merging consecutive ranges that end up with the
+    /// same value. The metadata is kept.
+    pub fn map<W: IpData>(self, mut f: impl FnMut(V) -> Option<W>) -> DbIpDatabase<W> {
+        DbIpDatabase {
+            #[cfg(feature = "ipv4")]
+            v4: self.v4.map(&mut f),
+            #[cfg(feature = "ipv6")]
+            v6: self.v6.map(&mut f),
+            metadata: self.metadata,
+        }
+    }
+
+    /// Keeps the values for which `keep` returns `true`, such as a few countries of interest,
+    /// and replaces the others with `other`, which may be `None`. This shrinks the database,
+    /// since consecutive ranges with the same value are merged.
+    ///
+    /// ```rust
+    /// # use db_ip_core::{CountryCode, DbIpDatabase};
+    /// # let db = DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv")).unwrap();
+    /// let north_america = ["US", "CA", "MX"].map(|cc| CountryCode::from_str(cc).unwrap());
+    /// let db = db.restrict(|cc| north_america.contains(cc), CountryCode::from_str("ZZ"));
+    /// ```
+    pub fn restrict(self, mut keep: impl FnMut(&V) -> bool, other: Option<V>) -> Self {
+        self.map(|v| if keep(&v) { Some(v) } else { other })
+    }
+
+    /// Removes all ranges except those of the given address family, for which lookups will
+    /// return `None`.
+    pub fn restrict_family(self, family: AddressFamily) -> Self {
+        Self {
+            #[cfg(feature = "ipv4")]
+            v4: if family == AddressFamily::Ipv4 {
+                self.v4
+            } else {
+                DbIpDatabaseInner::default()
+            },
+            #[cfg(feature = "ipv6")]
+            v6: if family == AddressFamily::Ipv6 {
+                self.v6
+            } else {
+                DbIpDatabaseInner::default()
+            },
+            metadata: self.metadata,
+        }
+    }
+}
+
+impl<IP, V> Default for DbIpDatabaseInner<IP, V> {
+    fn default() -> Self {
+        Self {
+            starts: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<IP: Copy + Default + PartialEq, V> DbIpDatabaseInner<IP, V> {
+    fn map<W: PartialEq>(self, f: &mut impl FnMut(V) -> Option<W>) -> DbIpDatabaseInner<IP, W> {
+        let mut inner = DbIpDatabaseInner::default();
+        for (start, value) in self.starts.into_iter().zip(self.values) {
+            let value = value.and_then(&mut *f);
+            if inner.values.last() != Some(&value) {
+                inner.starts.push(start);
+                inner.values.push(value);
+            }
+        }
+        // A single range without a value is equivalent to none at all.
+        if inner.starts == [IP::default()] && inner.values == [None] {
+            return DbIpDatabaseInner::default();
+        }
+        inner
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(
+    feature = "csv",
+    feature = "region",
+    feature = "ipv4",
+    feature = "ipv6"
+))]
+mod test {
+    use crate::{AddressFamily, CountryCode, DbIpDatabase, Region};
+
+    #[test]
+    fn restrict() {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv"))
+                .unwrap();
+        let us = CountryCode::from_str("US");
+        let zz = CountryCode::from_str("ZZ");
+
+        let db_len = db.len();
+        let restricted = db.restrict(|&cc| Some(cc) == us, zz);
+        assert_eq!(restricted.validate(), Ok(()));
+        assert_eq!(restricted.get(&"1.0.0.0".parse().unwrap()), zz);
+        assert_eq!(restricted.get(&"2a07:7ec5:8233::1".parse().unwrap()), zz);
+        assert_eq!(restricted.get(&"0.1.2.3".parse().unwrap()), us);
+        assert_eq!(restricted.get(&"255.255.255.255".parse().unwrap()), zz);
+        assert_eq!(restricted.len(), db_len);
+
+        let none = restricted.restrict(|_| false, None);
+        assert_eq!(none.len(), 0);
+        assert_eq!(none.get(&"0.1.2.3".parse().unwrap()), None);
+
+        let v6 = DbIpDatabase::<Region>::from_csv_str(include_str!("../../test_country_data.csv"))
+            .unwrap()
+            .restrict_family(AddressFamily::Ipv6);
+        assert_eq!(v6.len_v4(), 0);
+        assert_eq!(v6.get(&"1.0.0.0".parse().unwrap()), None);
+    }
+}
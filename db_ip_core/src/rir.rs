@@ -0,0 +1,138 @@
+//! Importer for the `delegated-*-extended` statistics published by regional internet registries.
+
+use crate::{value_from_country_code, DbIpDatabase, DbIpDatabaseBuilder, FromCsvError, IpData};
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+impl<V: IpData> DbIpDatabase<V> {
+    /// Load from RIR delegated statistics readers, e.g. `delegated-ripencc-extended-latest`.
+    ///
+    /// Only `allocated` and `assigned` `ipv4`/`ipv6` entries with a country code are considered.
+    /// Since each registry covers different address space, the entries of all readers are merged
+    /// before building the database. Overlapping entries result in
+    /// [`FromCsvError::AddrOutOfOrder`].
+    pub fn from_rir_delegated_readers<R: Read>(
+        readers: impl IntoIterator<Item = R>,
+    ) -> Result<Self, FromCsvError> {
+        let mut ranges = Vec::new();
+
+        for reader in readers {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .delimiter(b'|')
+                .comment(Some(b'#'))
+                .flexible(true)
+                .from_reader(reader);
+
+            for record in reader.records() {
+                let record = record.map_err(FromCsvError::Csv)?;
+                if let Some(range) = parse_entry(&record)? {
+                    ranges.push(range);
+                }
+            }
+        }
+
+        // Ipv4 sorts before Ipv6, so each address family ends up in ascending order.
+        ranges.sort_unstable_by_key(|&(begin, _, _)| begin);
+
+        let mut builder = DbIpDatabaseBuilder::new();
+        for (begin, end, value) in ranges {
+            builder.push(begin, end, value)?;
+        }
+        Ok(builder.build_bounded())
+    }
+
+    /// Load from RIR delegated statistics files. See [`DbIpDatabase::from_rir_delegated_readers`].
+    pub fn from_rir_delegated_files(paths: &[&str]) -> Result<Self, FromCsvError> {
+        let files = paths
+            .iter()
+            .map(std::fs::File::open)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| FromCsvError::Csv(e.into()))?;
+
+        Self::from_rir_delegated_readers(files.into_iter().map(std::io::BufReader::new))
+    }
+}
+
+/// Parses one `registry|cc|type|start|value|date|status[|opaque-id[|extensions]]` entry, returning
+/// [`None`] for entries that aren't relevant (version, summary, asn, unallocated, without a
+/// country code, etc.).
+fn parse_entry<V: IpData>(
+    record: &csv::StringRecord,
+) -> Result<Option<(IpAddr, IpAddr, V)>, FromCsvError> {
+    if record.len() < 7
+        || matches!(&record[1], "" | "*")
+        || !matches!(&record[6], "allocated" | "assigned")
+    {
+        return Ok(None);
+    }
+
+    let count_or_prefix = u32::from_str(&record[4]).map_err(|_| FromCsvError::InvalidRecord)?;
+    let (begin, end) = match &record[2] {
+        "ipv4" => {
+            let begin = Ipv4Addr::from_str(&record[3]).map_err(FromCsvError::AddrParse)?;
+            let end = count_or_prefix
+                .checked_sub(1)
+                .and_then(|n| u32::from(begin).checked_add(n))
+                .ok_or(FromCsvError::InvalidRecord)?;
+            (IpAddr::V4(begin), IpAddr::V4(Ipv4Addr::from(end)))
+        }
+        "ipv6" => {
+            let begin = Ipv6Addr::from_str(&record[3]).map_err(FromCsvError::AddrParse)?;
+            let host_mask = u128::MAX
+                .checked_shr(count_or_prefix)
+                .ok_or(FromCsvError::InvalidRecord)?;
+            if u128::from(begin) & host_mask != 0 {
+                return Err(FromCsvError::InvalidRecord);
+            }
+            let end = u128::from(begin) | host_mask;
+            (IpAddr::V6(begin), IpAddr::V6(Ipv6Addr::from(end)))
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(value_from_country_code(&record[1])?.map(|value| (begin, end, value)))
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "ipv4")]
+    use crate::{CountryCode, DbIpDatabase};
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+    fn rir_delegated() {
+        // Readers in arbitrary order.
+        let db = DbIpDatabase::<CountryCode>::from_rir_delegated_readers(vec![
+            include_str!("../test_data/delegated-arin-extended.txt").as_bytes(),
+            include_str!("../test_data/delegated-apnic-extended.txt").as_bytes(),
+        ])
+        .unwrap();
+
+        let get = |ip: &str| db.get(&ip.parse().unwrap());
+        assert_eq!(get("1.0.0.255"), CountryCode::from_str("AU"));
+        assert_eq!(get("1.0.1.0"), CountryCode::from_str("CN"));
+        assert_eq!(get("1.0.2.0"), None);
+        // Allocated, but without a country code.
+        assert_eq!(get("1.0.4.0"), None);
+        assert_eq!(get("3.255.255.255"), CountryCode::from_str("US"));
+        assert_eq!(get("4.0.0.99"), CountryCode::from_str("CA"));
+        assert_eq!(get("4.0.0.100"), None);
+        assert_eq!(get("2001:200:1fff::1"), CountryCode::from_str("JP"));
+        assert_eq!(get("2001:200:2000::"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ipv4")]
+    fn rir_overlap() {
+        let overlap = "ripencc|NL|ipv4|1.0.0.128|128|20110811|assigned|x\n";
+        assert!(
+            DbIpDatabase::<CountryCode>::from_rir_delegated_readers(vec![
+                include_str!("../test_data/delegated-apnic-extended.txt").as_bytes(),
+                overlap.as_bytes()
+            ])
+            .is_err()
+        );
+    }
+}
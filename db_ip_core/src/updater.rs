@@ -0,0 +1,237 @@
+//! Downloading the latest lite database from db-ip.com at runtime.
+
+use crate::lite::{lite_months, lite_url, LITE_BASE_URL};
+use crate::{DatabaseMetadata, DbIpDatabase, FromCsvError, IpData, ATTRIBUTION};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Downloads the most recent monthly lite database, in the same way as the build script of the
+/// `db_ip` crate, and stores it on disk. The database's [`DbIpDatabase::metadata`] describes the
+/// download.
+///
+/// ```rust,ignore
+/// let update = Updater::new("/var/lib/db_ip").update::<CountryCode>()?;
+/// println!("using {}-{:02}", update.year, update.month);
+/// ```
+pub struct Updater {
+    base_url: String,
+    edition: String,
+    dir: PathBuf,
+}
+
+/// A successfully downloaded and validated database.
+#[derive(Debug)]
+pub struct Update<V> {
+    /// The year of the downloaded month.
+    pub year: i32,
+    /// The downloaded month (1-12).
+    pub month: u32,
+    /// Where the decompressed CSV was stored.
+    pub path: PathBuf,
+    /// The database loaded from it.
+    pub database: DbIpDatabase<V>,
+}
+
+/// Errors that may arise when updating a database.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum UpdateError {
+    /// Failed to connect, or the server responded with an error other than 404 Not Found.
+    Http(reqwest::Error),
+    /// Failed to decompress or store the file.
+    Io(io::Error),
+    /// The downloaded file wasn't a valid database. The previously stored file, if any, is kept.
+    Csv(FromCsvError),
+    /// None of the candidate months were available.
+    NotFound,
+}
+
+impl Updater {
+    /// Stores databases in `dir`, which must exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_url: LITE_BASE_URL.to_owned(),
+            edition: "country".to_owned(),
+            dir: dir.into(),
+        }
+    }
+
+    /// Downloads from a different location than [`LITE_BASE_URL`], such as a mirror.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Downloads a different edition than `country`, such as `city`.
+    pub fn edition(mut self, edition: impl Into<String>) -> Self {
+        self.edition = edition.into();
+        self
+    }
+
+    /// Where the decompressed CSV is stored, e.g. `dir/dbip-country-lite.csv`. This doesn't
+    /// change from month to month, so it can be reloaded with [`DbIpDatabase::from_csv_file`].
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("dbip-{}-lite.csv", self.edition))
+    }
+
+    /// Downloads the most recent month that is available, replaces the stored file if it is
+    /// valid, and returns the database.
+    pub fn update<V: IpData>(&self) -> Result<Update<V>, UpdateError> {
+        let client = reqwest::blocking::Client::new();
+
+        for (year, month) in lite_months(SystemTime::now()) {
+            let url = lite_url(&self.base_url, &self.edition, (year, month));
+            let response = client.get(&url).send().map_err(UpdateError::Http)?;
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                continue;
+            }
+            let response = response.error_for_status().map_err(UpdateError::Http)?;
+
+            let path = self.path();
+            let tmp_path = self
+                .dir
+                .join(format!(".dbip-{}-lite.csv.tmp", self.edition));
+            let result = download(response, &tmp_path).and_then(|_| {
+                let path_str = tmp_path.to_str().ok_or_else(|| {
+                    UpdateError::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "non-UTF-8 path",
+                    ))
+                })?;
+                let database = DbIpDatabase::from_csv_file(path_str).map_err(UpdateError::Csv)?;
+                let built_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs());
+                let metadata = DatabaseMetadata {
+                    source_url: Some(url.clone().into()),
+                    data_month: Some((year, month)),
+                    built_at: Some(built_at),
+                    attribution: Some(ATTRIBUTION.into()),
+                    ..DatabaseMetadata::default()
+                };
+                std::fs::rename(&tmp_path, &path).map_err(UpdateError::Io)?;
+                Ok(database.with_metadata(metadata))
+            });
+            if result.is_err() {
+                let _ = std::fs::remove_file(&tmp_path);
+            }
+
+            return result.map(|database| Update {
+                year,
+                month,
+                path,
+                database,
+            });
+        }
+
+        Err(UpdateError::NotFound)
+    }
+}
+
+/// Decompresses `response` into a new file at `path`, and makes sure it reaches the disk.
+fn download(response: reqwest::blocking::Response, path: &Path) -> Result<(), UpdateError> {
+    let mut out = BufWriter::new(File::create(path).map_err(UpdateError::Io)?);
+    io::copy(&mut GzDecoder::new(response), &mut out).map_err(UpdateError::Io)?;
+    let out = out
+        .into_inner()
+        .map_err(|e| UpdateError::Io(e.into_error()))?;
+    out.sync_all().map_err(UpdateError::Io)
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use super::{UpdateError, Updater};
+    use crate::lite::lite_months;
+    use crate::CountryCode;
+    use flate2::write::GzEncoder;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::time::SystemTime;
+
+    /// Serves `body` for `available` paths, and 404 Not Found otherwise, until `requests` requests
+    /// have been served.
+    fn serve(available: Vec<String>, body: &'static [u8], requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&mut stream);
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split(' ').nth(1).unwrap().to_owned();
+                // Skip headers.
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                gz.write_all(body).unwrap();
+                let gz = gz.finish().unwrap();
+                if available.contains(&path) {
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        gz.len()
+                    )
+                    .unwrap();
+                    stream.write_all(&gz).unwrap();
+                } else {
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        )
+                        .unwrap();
+                }
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn update() {
+        let dir = std::env::temp_dir().join(format!("db_ip_updater_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // The current month isn't available yet.
+        let (year, month) = lite_months(SystemTime::now()).nth(1).unwrap();
+        let path = format!("/dbip-country-lite-{}-{:02}.csv.gz", year, month);
+        let csv = include_bytes!("../../test_country_data.csv");
+        let base_url = serve(vec![path.clone()], csv, 2);
+
+        let updater = Updater::new(&dir).base_url(base_url);
+        let update = updater.update::<CountryCode>().unwrap();
+        assert_eq!((update.year, update.month), (year, month));
+        assert_eq!(update.path, updater.path());
+        assert_eq!(std::fs::read(&update.path).unwrap(), csv);
+        assert_eq!(update.database.metadata().data_month, Some((year, month)));
+        assert_eq!(
+            update.database.get(&"1.2.3.4".parse().unwrap()),
+            CountryCode::from_str("AU")
+        );
+
+        // An invalid file doesn't replace the valid one.
+        let base_url = serve(vec![path], b"not,a,database,at,all", 2);
+        let updater = updater.base_url(base_url);
+        let result = updater.update::<CountryCode>();
+        assert!(matches!(result, Err(UpdateError::Csv(_))));
+        assert_eq!(std::fs::read(updater.path()).unwrap_or_default(), csv);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn not_found() {
+        let base_url = serve(Vec::new(), b"", 49);
+        let dir = std::env::temp_dir();
+        assert!(matches!(
+            Updater::new(dir).base_url(base_url).update::<CountryCode>(),
+            Err(UpdateError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::database_ref::RangesRef;
+#[cfg(feature = "serde")]
+use crate::{DatabaseMetadata, DbIpDatabaseInner};
+use crate::{DbIpDatabase, DbIpDatabaseRef, IpData};
+use core::fmt::{self, Display, Formatter};
+
+/// An Ipv4 or Ipv6 address family.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+/// An invariant of a [`DbIpDatabase`] that doesn't hold, e.g. because it was deserialized from
+/// a corrupted or malicious file. Indices refer to the range starts of the address family.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum InvalidDatabase {
+    /// There isn't exactly one value per range start.
+    LengthMismatch {
+        family: AddressFamily,
+        starts: usize,
+        values: usize,
+    },
+    /// The range start at `index` is less than the previous one.
+    Unsorted { family: AddressFamily, index: usize },
+    /// The range start at `index` equals the previous one.
+    DuplicateStart { family: AddressFamily, index: usize },
+    /// The range at `index` has the same value as the previous one, so they should have been
+    /// merged.
+    Unmerged { family: AddressFamily, index: usize },
+}
+
+impl Display for InvalidDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch {
+                family,
+                starts,
+                values,
+            } => write!(
+                f,
+                "{:?} ranges have {} starts but {} values",
+                family, starts, values
+            ),
+            Self::Unsorted { family, index } => write!(
+                f,
+                "{:?} range {} starts before the previous one",
+                family, index
+            ),
+            Self::DuplicateStart { family, index } => write!(
+                f,
+                "{:?} range {} starts at the same address as the previous one",
+                family, index
+            ),
+            Self::Unmerged { family, index } => write!(
+                f,
+                "{:?} range {} has the same value as the previous one",
+                family, index
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidDatabase {}
+
+impl<IP: Ord + Copy, V: IpData> RangesRef<'_, IP, V> {
+    fn validate(&self, family: AddressFamily) -> Result<(), InvalidDatabase> {
+        if self.starts.len() != self.values.len() {
+            return Err(InvalidDatabase::LengthMismatch {
+                family,
+                starts: self.starts.len(),
+                values: self.values.len(),
+            });
+        }
+        for index in 1..self.starts.len() {
+            if self.starts[index] < self.starts[index - 1] {
+                return Err(InvalidDatabase::Unsorted { family, index });
+            }
+            if self.starts[index] == self.starts[index - 1] {
+                return Err(InvalidDatabase::DuplicateStart { family, index });
+            }
+            if self.values[index] == self.values[index - 1] {
+                return Err(InvalidDatabase::Unmerged { family, index });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<V: IpData> DbIpDatabaseRef<'_, V> {
+    /// Checks the invariants that lookups rely on, which hold for any database that was built
+    /// by this crate. Deserialization checks them automatically.
+    pub fn validate(&self) -> Result<(), InvalidDatabase> {
+        #[cfg(feature = "ipv4")]
+        self.v4.validate(AddressFamily::Ipv4)?;
+        #[cfg(feature = "ipv6")]
+        self.v6.validate(AddressFamily::Ipv6)?;
+        Ok(())
+    }
+}
+
+impl<V: IpData> DbIpDatabase<V> {
+    /// Checks the invariants that lookups rely on, which hold for any database that was built
+    /// by this crate. Deserialization checks them automatically.
+    pub fn validate(&self) -> Result<(), InvalidDatabase> {
+        self.as_database_ref().validate()
+    }
+}
+
+/// Deserialized like [`DbIpDatabase`], but not yet validated.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "DbIpDatabase")]
+pub(crate) struct UncheckedDbIpDatabase<V> {
+    #[cfg(feature = "ipv4")]
+    pub v4: DbIpDatabaseInner<u32, V>,
+    #[cfg(feature = "ipv6")]
+    pub v6: DbIpDatabaseInner<u128, V>,
+}
+
+#[cfg(feature = "serde")]
+impl<V: IpData> UncheckedDbIpDatabase<V> {
+    pub fn validate(self) -> Result<DbIpDatabase<V>, InvalidDatabase> {
+        let db = DbIpDatabase {
+            #[cfg(feature = "ipv4")]
+            v4: self.v4,
+            #[cfg(feature = "ipv6")]
+            v6: self.v6,
+            metadata: DatabaseMetadata::default(),
+        };
+        db.validate().map(|_| db)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V: IpData + serde::Deserialize<'de>> serde::Deserialize<'de> for DbIpDatabase<V> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        UncheckedDbIpDatabase::deserialize(deserializer)?
+            .validate()
+            .map_err(|e| serde::de::Error::custom(format_args!("invalid database: {}", e)))
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "csv", feature = "ipv4"))]
+mod test {
+    use super::{AddressFamily, InvalidDatabase};
+    use crate::{CountryCode, DbIpDatabase, DbIpDatabaseRef};
+
+    #[test]
+    fn validate() {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../../test_country_data.csv"))
+                .unwrap();
+        assert_eq!(db.validate(), Ok(()));
+
+        let us = CountryCode::from_str("US");
+        let au = CountryCode::from_str("AU");
+        let invalid = |starts: &[u32], values: &[Option<CountryCode>]| {
+            DbIpDatabaseRef::from_parts(starts, values, &[], &[]).validate()
+        };
+        assert_eq!(
+            invalid(&[1, 0], &[us, au]),
+            Err(InvalidDatabase::Unsorted {
+                family: AddressFamily::Ipv4,
+                index: 1
+            })
+        );
+        assert_eq!(
+            invalid(&[0, 1, 1], &[us, au, us]),
+            Err(InvalidDatabase::DuplicateStart {
+                family: AddressFamily::Ipv4,
+                index: 2
+            })
+        );
+        assert_eq!(
+            invalid(&[0, 1, 2], &[us, None, None]),
+            Err(InvalidDatabase::Unmerged {
+                family: AddressFamily::Ipv4,
+                index: 2
+            })
+        );
+        assert_eq!(
+            invalid(&[1, 0], &[us, au]).unwrap_err().to_string(),
+            "Ipv4 range 1 starts before the previous one"
+        );
+    }
+}
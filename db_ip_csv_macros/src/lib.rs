@@ -0,0 +1,107 @@
+//! The proc macro behind `db_ip::include_database_from_csv!`.
+//!
+//! It builds databases with `db_ip_core`, which depends on `db_ip_macros` for its region table, so
+//! it can't live in `db_ip_macros` without a dependency cycle.
+
+#![cfg(any(feature = "ipv4", feature = "ipv6"))]
+
+use db_ip_core::{CountryCode, DbIpDatabase};
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+
+/// Reads a CSV file in the format of db-ip.com's country or city data, relative to the invoking
+/// crate's `Cargo.toml`, at compile time, builds a database of the given value type from it, and
+/// expands to the serialized database (see `DbIpDatabase::to_bytes`).
+///
+/// Takes the path of the crate that re-exports `db_ip_core` (e.g. `$crate`), the value type, and
+/// the path of the CSV file. Only the value types of `db_ip_core` are supported, since this crate
+/// can't depend on yours. Use `db_ip::include_database_from_csv!` instead, which deserializes the
+/// database.
+#[proc_macro]
+pub fn database_bytes_from_csv(stream: TokenStream) -> TokenStream {
+    let Input { krate, value, path } = syn::parse_macro_input!(stream as Input);
+    let full_path =
+        std::path::Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default())
+            .join(path.value());
+    let full_path = full_path.to_string_lossy();
+
+    let name = value_name(&value);
+    let (core_type, bytes) = match name.as_deref() {
+        Some("CountryCode") => (
+            "CountryCode",
+            DbIpDatabase::<CountryCode>::from_csv_file(&full_path).map(|db| db.to_bytes()),
+        ),
+        #[cfg(feature = "region")]
+        Some("Region") => (
+            "Region",
+            DbIpDatabase::<db_ip_core::Region>::from_csv_file(&full_path).map(|db| db.to_bytes()),
+        ),
+        _ => {
+            return syn::Error::new_spanned(
+                value,
+                "only CountryCode and Region databases can be built at compile time, use \
+                 `from_csv_str(include_str!(..))` for other value types",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+    let bytes = match bytes {
+        Ok(bytes) => proc_macro2::Literal::byte_string(&bytes),
+        Err(e) => {
+            return syn::Error::new(path.span(), format!("error reading {}: {:?}", full_path, e))
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    // The type is only matched by name, so make sure it is the core type, and not another type of
+    // the same name.
+    let core_type = syn::Ident::new(core_type, value.span());
+    let same_type = quote::quote_spanned! {value.span()=>
+        const _: fn(#value) -> #krate::#core_type = |value| value;
+    };
+
+    let result = quote! {{
+        // Rebuild if the file changes.
+        const _: &[u8] = include_bytes!(#full_path);
+        #same_type
+        #bytes
+    }};
+    result.into()
+}
+
+/// `krate, Value, "path"`
+struct Input {
+    krate: proc_macro2::TokenTree,
+    value: syn::Type,
+    path: syn::LitStr,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let value = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let path = input.parse()?;
+        Ok(Self { krate, value, path })
+    }
+}
+
+/// The last path segment of a value type, e.g. `Region` for `db_ip::Region`.
+fn value_name(value: &syn::Type) -> Option<String> {
+    match value {
+        syn::Type::Group(group) => value_name(&group.elem),
+        syn::Type::Paren(paren) => value_name(&paren.elem),
+        syn::Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .filter(|segment| segment.arguments.is_empty())
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,208 @@
+#ifndef DB_IP_H
+#define DB_IP_H
+
+/* Generated by cbindgen from db_ip_ffi/src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Stable integer equivalent of a region.
+ */
+typedef enum DbIpRegion {
+  /**
+   * No region is associated with the address.
+   */
+  DB_IP_REGION_UNKNOWN = 0,
+  DB_IP_REGION_AFRICA = 1,
+  DB_IP_REGION_ASIA = 2,
+  DB_IP_REGION_EUROPE = 3,
+  DB_IP_REGION_NORTH_AMERICA = 4,
+  DB_IP_REGION_OCEANIA = 5,
+  DB_IP_REGION_SOUTH_AMERICA = 6,
+} DbIpRegion;
+
+/**
+ * A database of two letter country codes.
+ */
+typedef struct DbIpCountryCodeDatabase DbIpCountryCodeDatabase;
+
+/**
+ * A database of regions.
+ */
+typedef struct DbIpRegionDatabase DbIpRegionDatabase;
+
+/**
+ * Loads the country code database embedded at build time. Returns `NULL` on failure.
+ */
+struct DbIpCountryCodeDatabase *db_ip_country_code_database_embedded(void);
+
+/**
+ * Loads a country code database from a (possibly gzip-compressed) db-ip.com CSV file. Returns
+ * `NULL` on failure.
+ *
+ * # Safety
+ *
+ * `path` must be `NULL` or a valid, nul-terminated string.
+ */
+struct DbIpCountryCodeDatabase *db_ip_country_code_database_from_csv_file(const char *path);
+
+/**
+ * Loads a country code database from a file written by `DbIpDatabase::to_bytes` of `db_ip`.
+ * Returns `NULL` on failure.
+ *
+ * # Safety
+ *
+ * `path` must be `NULL` or a valid, nul-terminated string.
+ */
+struct DbIpCountryCodeDatabase *db_ip_country_code_database_from_serialized_file(const char *path);
+
+/**
+ * Frees a country code database. Does nothing if `db` is `NULL`.
+ *
+ * # Safety
+ *
+ * `db` must be `NULL` or a handle that has not yet been freed.
+ */
+void db_ip_country_code_database_free(struct DbIpCountryCodeDatabase *db);
+
+/**
+ * Looks up the country code of an Ipv4 address, writing its two (uppercase ASCII) bytes to
+ * `country_code_out`. Returns whether a country code was found.
+ *
+ * # Safety
+ *
+ * `db` must be a valid handle, `ip` must point to 4 bytes and `country_code_out` must be
+ * `NULL` or point to 2 writable bytes.
+ */
+bool db_ip_country_code_lookup_v4(const struct DbIpCountryCodeDatabase *db,
+                                  const uint8_t *ip,
+                                  char *country_code_out);
+
+/**
+ * Looks up the country code of an Ipv6 address, writing its two (uppercase ASCII) bytes to
+ * `country_code_out`. Returns whether a country code was found.
+ *
+ * # Safety
+ *
+ * `db` must be a valid handle, `ip` must point to 16 bytes and `country_code_out` must be
+ * `NULL` or point to 2 writable bytes.
+ */
+bool db_ip_country_code_lookup_v6(const struct DbIpCountryCodeDatabase *db,
+                                  const uint8_t *ip,
+                                  char *country_code_out);
+
+/**
+ * Like [`db_ip_country_code_lookup_v4`], but also writes the first and last address (4 bytes
+ * each) of the range containing `ip` to `start_out` and `end_out`.
+ *
+ * # Safety
+ *
+ * See [`db_ip_country_code_lookup_v4`]. `start_out` and `end_out` must be `NULL` or point to 4
+ * writable bytes.
+ */
+bool db_ip_country_code_range_v4(const struct DbIpCountryCodeDatabase *db,
+                                 const uint8_t *ip,
+                                 uint8_t *start_out,
+                                 uint8_t *end_out,
+                                 char *country_code_out);
+
+/**
+ * Like [`db_ip_country_code_lookup_v6`], but also writes the first and last address (16 bytes
+ * each) of the range containing `ip` to `start_out` and `end_out`.
+ *
+ * # Safety
+ *
+ * See [`db_ip_country_code_lookup_v6`]. `start_out` and `end_out` must be `NULL` or point to 16
+ * writable bytes.
+ */
+bool db_ip_country_code_range_v6(const struct DbIpCountryCodeDatabase *db,
+                                 const uint8_t *ip,
+                                 uint8_t *start_out,
+                                 uint8_t *end_out,
+                                 char *country_code_out);
+
+/**
+ * Loads the region database embedded at build time. Returns `NULL` on failure.
+ */
+struct DbIpRegionDatabase *db_ip_region_database_embedded(void);
+
+/**
+ * Loads a region database from a (possibly gzip-compressed) db-ip.com CSV file. Returns `NULL`
+ * on failure.
+ *
+ * # Safety
+ *
+ * `path` must be `NULL` or a valid, nul-terminated string.
+ */
+struct DbIpRegionDatabase *db_ip_region_database_from_csv_file(const char *path);
+
+/**
+ * Loads a region database from a file written by `DbIpDatabase::to_bytes` of `db_ip`. Returns
+ * `NULL` on failure.
+ *
+ * # Safety
+ *
+ * `path` must be `NULL` or a valid, nul-terminated string.
+ */
+struct DbIpRegionDatabase *db_ip_region_database_from_serialized_file(const char *path);
+
+/**
+ * Frees a region database. Does nothing if `db` is `NULL`.
+ *
+ * # Safety
+ *
+ * `db` must be `NULL` or a handle that has not yet been freed.
+ */
+void db_ip_region_database_free(struct DbIpRegionDatabase *db);
+
+/**
+ * Looks up the region of an Ipv4 address.
+ *
+ * # Safety
+ *
+ * `db` must be a valid handle and `ip` must point to 4 bytes.
+ */
+enum DbIpRegion db_ip_region_lookup_v4(const struct DbIpRegionDatabase *db, const uint8_t *ip);
+
+/**
+ * Looks up the region of an Ipv6 address.
+ *
+ * # Safety
+ *
+ * `db` must be a valid handle and `ip` must point to 16 bytes.
+ */
+enum DbIpRegion db_ip_region_lookup_v6(const struct DbIpRegionDatabase *db, const uint8_t *ip);
+
+/**
+ * Like [`db_ip_region_lookup_v4`], but also writes the first and last address (4 bytes each)
+ * of the range containing `ip` to `start_out` and `end_out`, unless the region is unknown.
+ *
+ * # Safety
+ *
+ * See [`db_ip_region_lookup_v4`]. `start_out` and `end_out` must be `NULL` or point to 4
+ * writable bytes.
+ */
+enum DbIpRegion db_ip_region_range_v4(const struct DbIpRegionDatabase *db,
+                                      const uint8_t *ip,
+                                      uint8_t *start_out,
+                                      uint8_t *end_out);
+
+/**
+ * Like [`db_ip_region_lookup_v6`], but also writes the first and last address (16 bytes each)
+ * of the range containing `ip` to `start_out` and `end_out`, unless the region is unknown.
+ *
+ * # Safety
+ *
+ * See [`db_ip_region_lookup_v6`]. `start_out` and `end_out` must be `NULL` or point to 16
+ * writable bytes.
+ */
+enum DbIpRegion db_ip_region_range_v6(const struct DbIpRegionDatabase *db,
+                                      const uint8_t *ip,
+                                      uint8_t *start_out,
+                                      uint8_t *end_out);
+
+#endif  /* DB_IP_H */
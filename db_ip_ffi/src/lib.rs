@@ -0,0 +1,364 @@
+//! C bindings for [`db_ip`], declared in `include/db_ip.h`.
+//!
+//! Databases are opaque handles, which must be freed with the corresponding `_free` function.
+//! Addresses are passed as network order bytes (4 for Ipv4, 16 for Ipv6).
+
+use db_ip::{CountryCode, DbIpDatabase, Region};
+use std::ffi::CStr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+use std::os::raw::c_char;
+
+/// A database of two letter country codes.
+pub struct DbIpCountryCodeDatabase(DbIpDatabase<CountryCode>);
+
+/// A database of regions.
+pub struct DbIpRegionDatabase(DbIpDatabase<Region>);
+
+/// Stable integer equivalent of a region.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DbIpRegion {
+    /// No region is associated with the address.
+    Unknown = 0,
+    Africa = 1,
+    Asia = 2,
+    Europe = 3,
+    NorthAmerica = 4,
+    Oceania = 5,
+    SouthAmerica = 6,
+}
+
+impl From<Option<Region>> for DbIpRegion {
+    fn from(region: Option<Region>) -> Self {
+        match region {
+            None => Self::Unknown,
+            Some(Region::Africa) => Self::Africa,
+            Some(Region::Asia) => Self::Asia,
+            Some(Region::Europe) => Self::Europe,
+            Some(Region::NorthAmerica) => Self::NorthAmerica,
+            Some(Region::Oceania) => Self::Oceania,
+            Some(Region::SouthAmerica) => Self::SouthAmerica,
+        }
+    }
+}
+
+/// Loads the country code database embedded at build time. Returns `NULL` on failure.
+#[no_mangle]
+pub extern "C" fn db_ip_country_code_database_embedded() -> *mut DbIpCountryCodeDatabase {
+    let db = db_ip::try_include_country_code_database!().ok();
+    into_handle(db.map(DbIpCountryCodeDatabase))
+}
+
+/// Loads a country code database from a (possibly gzip-compressed) db-ip.com CSV file. Returns
+/// `NULL` on failure.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_database_from_csv_file(
+    path: *const c_char,
+) -> *mut DbIpCountryCodeDatabase {
+    let db = path_str(path).and_then(|path| DbIpDatabase::from_csv_file(path).ok());
+    into_handle(db.map(DbIpCountryCodeDatabase))
+}
+
+/// Loads a country code database from a file written by `DbIpDatabase::to_bytes` of `db_ip`.
+/// Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_database_from_serialized_file(
+    path: *const c_char,
+) -> *mut DbIpCountryCodeDatabase {
+    let db = path_str(path)
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| DbIpDatabase::from_bytes(&bytes).ok());
+    into_handle(db.map(DbIpCountryCodeDatabase))
+}
+
+/// Frees a country code database. Does nothing if `db` is `NULL`.
+///
+/// # Safety
+///
+/// `db` must be `NULL` or a handle that has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_database_free(db: *mut DbIpCountryCodeDatabase) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+/// Looks up the country code of an Ipv4 address, writing its two (uppercase ASCII) bytes to
+/// `country_code_out`. Returns whether a country code was found.
+///
+/// # Safety
+///
+/// `db` must be a valid handle, `ip` must point to 4 bytes and `country_code_out` must be
+/// `NULL` or point to 2 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_lookup_v4(
+    db: *const DbIpCountryCodeDatabase,
+    ip: *const u8,
+    country_code_out: *mut c_char,
+) -> bool {
+    let found = db
+        .as_ref()
+        .zip(read_v4(ip))
+        .and_then(|(db, ip)| db.0.get_v4(&ip));
+    write_country_code(found, country_code_out)
+}
+
+/// Looks up the country code of an Ipv6 address, writing its two (uppercase ASCII) bytes to
+/// `country_code_out`. Returns whether a country code was found.
+///
+/// # Safety
+///
+/// `db` must be a valid handle, `ip` must point to 16 bytes and `country_code_out` must be
+/// `NULL` or point to 2 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_lookup_v6(
+    db: *const DbIpCountryCodeDatabase,
+    ip: *const u8,
+    country_code_out: *mut c_char,
+) -> bool {
+    let found = db
+        .as_ref()
+        .zip(read_v6(ip))
+        .and_then(|(db, ip)| db.0.get_v6(&ip));
+    write_country_code(found, country_code_out)
+}
+
+/// Like [`db_ip_country_code_lookup_v4`], but also writes the first and last address (4 bytes
+/// each) of the range containing `ip` to `start_out` and `end_out`.
+///
+/// # Safety
+///
+/// See [`db_ip_country_code_lookup_v4`]. `start_out` and `end_out` must be `NULL` or point to 4
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_range_v4(
+    db: *const DbIpCountryCodeDatabase,
+    ip: *const u8,
+    start_out: *mut u8,
+    end_out: *mut u8,
+    country_code_out: *mut c_char,
+) -> bool {
+    let found = db
+        .as_ref()
+        .zip(read_v4(ip))
+        .and_then(|(db, ip)| db.0.get_range_v4(&ip));
+    write_range_v4(found.as_ref().map(|(range, _)| range), start_out, end_out);
+    write_country_code(found.map(|(_, cc)| cc), country_code_out)
+}
+
+/// Like [`db_ip_country_code_lookup_v6`], but also writes the first and last address (16 bytes
+/// each) of the range containing `ip` to `start_out` and `end_out`.
+///
+/// # Safety
+///
+/// See [`db_ip_country_code_lookup_v6`]. `start_out` and `end_out` must be `NULL` or point to 16
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_country_code_range_v6(
+    db: *const DbIpCountryCodeDatabase,
+    ip: *const u8,
+    start_out: *mut u8,
+    end_out: *mut u8,
+    country_code_out: *mut c_char,
+) -> bool {
+    let found = db
+        .as_ref()
+        .zip(read_v6(ip))
+        .and_then(|(db, ip)| db.0.get_range_v6(&ip));
+    write_range_v6(found.as_ref().map(|(range, _)| range), start_out, end_out);
+    write_country_code(found.map(|(_, cc)| cc), country_code_out)
+}
+
+/// Loads the region database embedded at build time. Returns `NULL` on failure.
+#[no_mangle]
+pub extern "C" fn db_ip_region_database_embedded() -> *mut DbIpRegionDatabase {
+    let db = db_ip::try_include_region_database!().ok();
+    into_handle(db.map(DbIpRegionDatabase))
+}
+
+/// Loads a region database from a (possibly gzip-compressed) db-ip.com CSV file. Returns `NULL`
+/// on failure.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_database_from_csv_file(
+    path: *const c_char,
+) -> *mut DbIpRegionDatabase {
+    let db = path_str(path).and_then(|path| DbIpDatabase::from_csv_file(path).ok());
+    into_handle(db.map(DbIpRegionDatabase))
+}
+
+/// Loads a region database from a file written by `DbIpDatabase::to_bytes` of `db_ip`. Returns
+/// `NULL` on failure.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a valid, nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_database_from_serialized_file(
+    path: *const c_char,
+) -> *mut DbIpRegionDatabase {
+    let db = path_str(path)
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| DbIpDatabase::from_bytes(&bytes).ok());
+    into_handle(db.map(DbIpRegionDatabase))
+}
+
+/// Frees a region database. Does nothing if `db` is `NULL`.
+///
+/// # Safety
+///
+/// `db` must be `NULL` or a handle that has not yet been freed.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_database_free(db: *mut DbIpRegionDatabase) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+/// Looks up the region of an Ipv4 address.
+///
+/// # Safety
+///
+/// `db` must be a valid handle and `ip` must point to 4 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_lookup_v4(
+    db: *const DbIpRegionDatabase,
+    ip: *const u8,
+) -> DbIpRegion {
+    DbIpRegion::from(
+        db.as_ref()
+            .zip(read_v4(ip))
+            .and_then(|(db, ip)| db.0.get_v4(&ip)),
+    )
+}
+
+/// Looks up the region of an Ipv6 address.
+///
+/// # Safety
+///
+/// `db` must be a valid handle and `ip` must point to 16 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_lookup_v6(
+    db: *const DbIpRegionDatabase,
+    ip: *const u8,
+) -> DbIpRegion {
+    DbIpRegion::from(
+        db.as_ref()
+            .zip(read_v6(ip))
+            .and_then(|(db, ip)| db.0.get_v6(&ip)),
+    )
+}
+
+/// Like [`db_ip_region_lookup_v4`], but also writes the first and last address (4 bytes each)
+/// of the range containing `ip` to `start_out` and `end_out`, unless the region is unknown.
+///
+/// # Safety
+///
+/// See [`db_ip_region_lookup_v4`]. `start_out` and `end_out` must be `NULL` or point to 4
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_range_v4(
+    db: *const DbIpRegionDatabase,
+    ip: *const u8,
+    start_out: *mut u8,
+    end_out: *mut u8,
+) -> DbIpRegion {
+    let found = db
+        .as_ref()
+        .zip(read_v4(ip))
+        .and_then(|(db, ip)| db.0.get_range_v4(&ip));
+    write_range_v4(found.as_ref().map(|(range, _)| range), start_out, end_out);
+    DbIpRegion::from(found.map(|(_, region)| region))
+}
+
+/// Like [`db_ip_region_lookup_v6`], but also writes the first and last address (16 bytes each)
+/// of the range containing `ip` to `start_out` and `end_out`, unless the region is unknown.
+///
+/// # Safety
+///
+/// See [`db_ip_region_lookup_v6`]. `start_out` and `end_out` must be `NULL` or point to 16
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn db_ip_region_range_v6(
+    db: *const DbIpRegionDatabase,
+    ip: *const u8,
+    start_out: *mut u8,
+    end_out: *mut u8,
+) -> DbIpRegion {
+    let found = db
+        .as_ref()
+        .zip(read_v6(ip))
+        .and_then(|(db, ip)| db.0.get_range_v6(&ip));
+    write_range_v6(found.as_ref().map(|(range, _)| range), start_out, end_out);
+    DbIpRegion::from(found.map(|(_, region)| region))
+}
+
+fn into_handle<T>(db: Option<T>) -> *mut T {
+    db.map_or(std::ptr::null_mut(), |db| Box::into_raw(Box::new(db)))
+}
+
+unsafe fn path_str<'a>(path: *const c_char) -> Option<&'a str> {
+    if path.is_null() {
+        None
+    } else {
+        CStr::from_ptr(path).to_str().ok()
+    }
+}
+
+unsafe fn read_v4(ip: *const u8) -> Option<Ipv4Addr> {
+    if ip.is_null() {
+        None
+    } else {
+        Some(Ipv4Addr::from(*(ip as *const [u8; 4])))
+    }
+}
+
+unsafe fn read_v6(ip: *const u8) -> Option<Ipv6Addr> {
+    if ip.is_null() {
+        None
+    } else {
+        Some(Ipv6Addr::from(*(ip as *const [u8; 16])))
+    }
+}
+
+unsafe fn write_country_code(country_code: Option<CountryCode>, out: *mut c_char) -> bool {
+    match country_code {
+        Some(country_code) => {
+            write_bytes(country_code.as_str().as_bytes(), out as *mut u8);
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe fn write_range_v4(range: Option<&RangeInclusive<Ipv4Addr>>, start: *mut u8, end: *mut u8) {
+    if let Some(range) = range {
+        write_bytes(&range.start().octets(), start);
+        write_bytes(&range.end().octets(), end);
+    }
+}
+
+unsafe fn write_range_v6(range: Option<&RangeInclusive<Ipv6Addr>>, start: *mut u8, end: *mut u8) {
+    if let Some(range) = range {
+        write_bytes(&range.start().octets(), start);
+        write_bytes(&range.end().octets(), end);
+    }
+}
+
+unsafe fn write_bytes(bytes: &[u8], out: *mut u8) {
+    if !out.is_null() {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * Exercises include/db_ip.h. Usage: test <path to test_country_data.csv> <country code database>
+ * <region database> <scratch file path>, where the databases were serialized from the same CSV.
+ */
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "db_ip.h"
+
+static void country_code_from_csv(const char *csv_path) {
+    DbIpCountryCodeDatabase *db = db_ip_country_code_database_from_csv_file(csv_path);
+    assert(db != NULL);
+
+    const uint8_t au_v4[4] = {1, 2, 3, 4};
+    char country_code[2];
+    assert(db_ip_country_code_lookup_v4(db, au_v4, country_code));
+    assert(memcmp(country_code, "AU", 2) == 0);
+
+    uint8_t start[4], end[4];
+    assert(db_ip_country_code_range_v4(db, au_v4, start, end, country_code));
+    const uint8_t expected_start[4] = {1, 0, 0, 0};
+    /* The last range extends to the last address. */
+    const uint8_t expected_end[4] = {255, 255, 255, 255};
+    assert(memcmp(start, expected_start, 4) == 0);
+    assert(memcmp(end, expected_end, 4) == 0);
+
+    const uint8_t unknown_v4[4] = {0, 0, 0, 0};
+    assert(!db_ip_country_code_lookup_v4(db, unknown_v4, country_code));
+
+    /* 2a07:7ec5:8233::1 */
+    const uint8_t au_v6[16] = {0x2a, 0x07, 0x7e, 0xc5, 0x82, 0x33, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1};
+    assert(db_ip_country_code_lookup_v6(db, au_v6, country_code));
+    assert(memcmp(country_code, "AU", 2) == 0);
+
+    uint8_t start_v6[16], end_v6[16];
+    assert(db_ip_country_code_range_v6(db, au_v6, start_v6, end_v6, NULL));
+    assert(memcmp(start_v6, au_v6, 6) == 0 && start_v6[15] == 0);
+    assert(end_v6[6] == 0xff && end_v6[15] == 0xff);
+
+    db_ip_country_code_database_free(db);
+}
+
+static void region_from_csv(const char *csv_path) {
+    DbIpRegionDatabase *db = db_ip_region_database_from_csv_file(csv_path);
+    assert(db != NULL);
+
+    const uint8_t au_v4[4] = {1, 2, 3, 4};
+    assert(db_ip_region_lookup_v4(db, au_v4) == DB_IP_REGION_OCEANIA);
+
+    uint8_t start[4], end[4];
+    assert(db_ip_region_range_v4(db, au_v4, start, end) == DB_IP_REGION_OCEANIA);
+    assert(start[0] == 1 && end[0] == 255 && end[3] == 255);
+
+    const uint8_t unknown_v4[4] = {0, 0, 0, 0};
+    assert(db_ip_region_lookup_v4(db, unknown_v4) == DB_IP_REGION_UNKNOWN);
+
+    db_ip_region_database_free(db);
+}
+
+static void country_code_from_serialized(const char *path) {
+    DbIpCountryCodeDatabase *db = db_ip_country_code_database_from_serialized_file(path);
+    assert(db != NULL);
+
+    const uint8_t au_v4[4] = {1, 2, 3, 4};
+    char country_code[2];
+    assert(db_ip_country_code_lookup_v4(db, au_v4, country_code));
+    assert(memcmp(country_code, "AU", 2) == 0);
+
+    const uint8_t unknown_v4[4] = {0, 0, 0, 0};
+    assert(!db_ip_country_code_lookup_v4(db, unknown_v4, country_code));
+
+    db_ip_country_code_database_free(db);
+}
+
+static void region_from_serialized(const char *path) {
+    DbIpRegionDatabase *db = db_ip_region_database_from_serialized_file(path);
+    assert(db != NULL);
+
+    const uint8_t au_v4[4] = {1, 2, 3, 4};
+    assert(db_ip_region_lookup_v4(db, au_v4) == DB_IP_REGION_OCEANIA);
+
+    db_ip_region_database_free(db);
+}
+
+static void embedded(void) {
+    const uint8_t us_v4[4] = {100, 128, 0, 0};
+
+    DbIpCountryCodeDatabase *country_code_db = db_ip_country_code_database_embedded();
+    assert(country_code_db != NULL);
+    char country_code[2];
+    assert(db_ip_country_code_lookup_v4(country_code_db, us_v4, country_code));
+    assert(memcmp(country_code, "US", 2) == 0);
+    db_ip_country_code_database_free(country_code_db);
+
+    DbIpRegionDatabase *region_db = db_ip_region_database_embedded();
+    assert(region_db != NULL);
+    assert(db_ip_region_lookup_v4(region_db, us_v4) == DB_IP_REGION_NORTH_AMERICA);
+    db_ip_region_database_free(region_db);
+}
+
+static void invalid(const char *scratch_path) {
+    assert(db_ip_country_code_database_from_csv_file(NULL) == NULL);
+    assert(db_ip_country_code_database_from_csv_file("does/not/exist.csv") == NULL);
+    assert(db_ip_region_database_from_serialized_file("does/not/exist.bin") == NULL);
+
+    FILE *scratch = fopen(scratch_path, "w");
+    assert(scratch != NULL);
+    fputs("not a database", scratch);
+    fclose(scratch);
+    assert(db_ip_country_code_database_from_serialized_file(scratch_path) == NULL);
+
+    db_ip_country_code_database_free(NULL);
+    db_ip_region_database_free(NULL);
+}
+
+int main(int argc, char **argv) {
+    assert(argc == 5);
+    country_code_from_csv(argv[1]);
+    region_from_csv(argv[1]);
+    country_code_from_serialized(argv[2]);
+    region_from_serialized(argv[3]);
+    embedded();
+    invalid(argv[4]);
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,54 @@
+use db_ip::{CountryCode, DbIpDatabase, Region};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Compiles `tests/c/test.c` against `include/db_ip.h` and the static library, and runs it on
+/// the test CSV and databases serialized from it.
+#[test]
+#[cfg(unix)]
+fn c_api() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    // `cargo test` builds the static library next to this test binary, in the `deps` directory.
+    // The copy above it is only updated by `cargo build` and may be stale.
+    let lib_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_owned();
+    let exe = out_dir.join("db_ip_c_api_test");
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| String::from("cc")))
+        .arg(crate_dir.join("tests/c/test.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(lib_dir.join("libdb_ip_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&exe)
+        .status()
+        .expect("failed to run C compiler");
+    assert!(status.success(), "failed to compile tests/c/test.c");
+
+    let csv = crate_dir.join("../test_country_data.csv");
+    let csv = csv.to_str().unwrap();
+    let country_code = out_dir.join("db_ip_c_api_test_country_code.bin");
+    let country_code_db = DbIpDatabase::<CountryCode>::from_csv_file(csv).unwrap();
+    std::fs::write(&country_code, country_code_db.to_bytes()).unwrap();
+    let region = out_dir.join("db_ip_c_api_test_region.bin");
+    let region_db = DbIpDatabase::<Region>::from_csv_file(csv).unwrap();
+    std::fs::write(&region, region_db.to_bytes()).unwrap();
+
+    let output = Command::new(&exe)
+        .arg(csv)
+        .arg(&country_code)
+        .arg(&region)
+        .arg(out_dir.join("db_ip_c_api_test.bin"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "tests/c/test.c failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,25 @@
+use std::fs;
+use std::path::Path;
+
+/// `include/db_ip.h` is generated with cbindgen. Run with `DB_IP_FFI_BLESS=1` to regenerate it.
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = env!("CARGO_MANIFEST_DIR");
+    let path = Path::new(crate_dir).join("include/db_ip.h");
+
+    let mut generated = Vec::new();
+    cbindgen::generate(crate_dir)
+        .expect("failed to generate header")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    if std::env::var_os("DB_IP_FFI_BLESS").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap_or_default(),
+        generated,
+        "include/db_ip.h is out of date, run with DB_IP_FFI_BLESS=1 to regenerate it"
+    );
+}
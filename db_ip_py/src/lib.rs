@@ -0,0 +1,128 @@
+//! Python bindings for [`db_ip`], as the `db_ip` extension module.
+//!
+//! Addresses may be given as strings or `ipaddress` objects. Country codes are returned as two
+//! letter strings, regions as their names (e.g. `"NorthAmerica"`), and unknown addresses as
+//! `None`.
+
+use db_ip::{CountryCode, DbIpDatabase, IpData, Region};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A database of two letter country codes.
+#[pyclass(name = "CountryCodeDatabase", module = "db_ip", frozen)]
+pub struct PyCountryCodeDatabase(DbIpDatabase<CountryCode>);
+
+/// A database of broad regions, roughly corresponding to populated continents.
+#[pyclass(name = "RegionDatabase", module = "db_ip", frozen)]
+pub struct PyRegionDatabase(DbIpDatabase<Region>);
+
+/// Generates the Python methods shared by both database classes.
+macro_rules! database_methods {
+    ($class:ident, $name:literal, $embedded:expr) => {
+        #[pymethods]
+        impl $class {
+            /// Loads the database embedded at build time.
+            #[staticmethod]
+            fn embedded() -> PyResult<Self> {
+                $embedded.map(Self).map_err(|e| {
+                    PyRuntimeError::new_err(format!("failed to load embedded database: {:?}", e))
+                })
+            }
+
+            /// Loads from a (possibly gzip-compressed) db-ip.com CSV file.
+            #[staticmethod]
+            fn from_csv_file(py: Python<'_>, path: &str) -> PyResult<Self> {
+                py.detach(|| DbIpDatabase::from_csv_file(path))
+                    .map(Self)
+                    .map_err(|e| PyValueError::new_err(format!("failed to load CSV: {:?}", e)))
+            }
+
+            /// Loads from CSV contained in a string.
+            #[staticmethod]
+            fn from_csv_str(csv: &str) -> PyResult<Self> {
+                DbIpDatabase::from_csv_str(csv)
+                    .map(Self)
+                    .map_err(|e| PyValueError::new_err(format!("failed to load CSV: {:?}", e)))
+            }
+
+            /// Gets the value associated with an address, if any.
+            fn get(&self, ip: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+                Ok(self.0.get(&extract_ip(ip)?).map(|v| v.to_string()))
+            }
+
+            /// Gets the values associated with each of an iterable of addresses.
+            fn get_many(&self, ips: &Bound<'_, PyAny>) -> PyResult<Vec<Option<String>>> {
+                ips.try_iter()?
+                    .map(|ip| Ok(self.0.get(&extract_ip(&ip?)?).map(|v| v.to_string())))
+                    .collect()
+            }
+
+            /// Gets the value associated with an address, if any, along with the first and last
+            /// address of the range it applies to, as `(first, last, value)`.
+            fn get_range(
+                &self,
+                ip: &Bound<'_, PyAny>,
+            ) -> PyResult<Option<(String, String, String)>> {
+                Ok(self.0.get_range(&extract_ip(ip)?).map(range_tuple))
+            }
+
+            /// Lists the address ranges that have a value, in ascending order, Ipv4 first, as
+            /// `(first, last, value)`.
+            fn ranges(&self) -> Vec<(String, String, String)> {
+                self.0.iter().map(range_tuple).collect()
+            }
+
+            /// Number of ranges stored, including those without a value.
+            fn __len__(&self) -> usize {
+                self.0.len()
+            }
+
+            fn __repr__(&self) -> String {
+                format!("<{} with {} ranges>", $name, self.0.len())
+            }
+        }
+    };
+}
+
+database_methods!(
+    PyCountryCodeDatabase,
+    "CountryCodeDatabase",
+    db_ip::try_include_country_code_database!()
+);
+database_methods!(
+    PyRegionDatabase,
+    "RegionDatabase",
+    db_ip::try_include_region_database!()
+);
+
+/// Accepts a string or an `ipaddress.IPv4Address`/`ipaddress.IPv6Address`.
+fn extract_ip(ip: &Bound<'_, PyAny>) -> PyResult<IpAddr> {
+    if let Ok(string) = ip.cast::<PyString>() {
+        IpAddr::from_str(string.to_str()?)
+            .map_err(|e| PyValueError::new_err(format!("invalid address {:?}: {}", string, e)))
+    } else {
+        ip.extract::<IpAddr>()
+    }
+}
+
+fn range_tuple<V: IpData + ToString>(
+    (range, value): (std::ops::RangeInclusive<IpAddr>, V),
+) -> (String, String, String) {
+    (
+        range.start().to_string(),
+        range.end().to_string(),
+        value.to_string(),
+    )
+}
+
+/// An (unofficial) library for querying db-ip.com data.
+#[pymodule]
+#[pyo3(name = "db_ip")]
+pub fn db_ip_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCountryCodeDatabase>()?;
+    m.add_class::<PyRegionDatabase>()?;
+    Ok(())
+}
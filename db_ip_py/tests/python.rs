@@ -0,0 +1,22 @@
+use db_ip_py::db_ip_py;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CString;
+use std::path::Path;
+
+/// Runs `tests/test_db_ip.py` under the local Python interpreter, with the module linked in.
+#[test]
+fn python_tests() {
+    pyo3::append_to_inittab!(db_ip_py);
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test_db_ip.py");
+    let code = CString::new(std::fs::read_to_string(&path).unwrap()).unwrap();
+
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("__name__", "__main__")?;
+        globals.set_item("__file__", path.to_str().unwrap())?;
+        py.run(&code, Some(&globals), None)
+    })
+    .unwrap_or_else(|e| panic!("tests/test_db_ip.py failed: {}", e));
+}
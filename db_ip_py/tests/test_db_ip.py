@@ -0,0 +1,72 @@
+"""Tests for the db_ip extension module.
+
+Run with `cargo test`, or with a local interpreter after `maturin develop`:
+
+    python tests/test_db_ip.py
+"""
+
+import ipaddress
+import os
+
+import db_ip
+
+CSV_PATH = os.path.join(os.path.dirname(os.path.abspath(__file__)), "..", "..", "test_country_data.csv")
+
+
+def test_country_code_from_csv():
+    db = db_ip.CountryCodeDatabase.from_csv_file(CSV_PATH)
+    assert db.get("1.2.3.4") == "AU"
+    assert db.get(ipaddress.ip_address("1.2.3.4")) == "AU"
+    assert db.get("2a07:7ec5:8225::1") == "US"
+    assert db.get("0.0.0.0") is None
+    assert len(db) > 0
+
+
+def test_region_from_csv():
+    db = db_ip.RegionDatabase.from_csv_file(CSV_PATH)
+    assert db.get("1.2.3.4") == "Oceania"
+    assert db.get("0.0.0.1") == "NorthAmerica"
+
+
+def test_get_many():
+    db = db_ip.CountryCodeDatabase.from_csv_file(CSV_PATH)
+    ips = ["1.2.3.4", ipaddress.ip_address("0.0.0.1"), "0.0.0.0"]
+    assert db.get_many(ips) == ["AU", "US", None]
+    assert db.get_many(ip for ip in ips) == ["AU", "US", None]
+
+
+def test_ranges():
+    db = db_ip.CountryCodeDatabase.from_csv_str("1.0.0.0,1.0.0.255,AU\n1.0.1.0,1.0.1.255,US\n")
+    assert db.ranges() == [("1.0.0.0", "1.0.0.255", "AU"), ("1.0.1.0", "255.255.255.255", "US")]
+    assert db.get_range("1.0.1.7") == ("1.0.1.0", "255.255.255.255", "US")
+    assert db.get_range("0.0.0.7") is None
+    assert repr(db) == "<CountryCodeDatabase with {} ranges>".format(len(db))
+
+
+def test_embedded():
+    assert db_ip.CountryCodeDatabase.embedded().get("100.128.0.0") == "US"
+    assert db_ip.RegionDatabase.embedded().get("100.128.0.0") == "NorthAmerica"
+
+
+def test_invalid():
+    db = db_ip.CountryCodeDatabase.from_csv_file(CSV_PATH)
+    for invalid in ["not an ip", 42]:
+        try:
+            db.get(invalid)
+        except (ValueError, TypeError):
+            pass
+        else:
+            raise AssertionError("expected error for {!r}".format(invalid))
+    try:
+        db_ip.CountryCodeDatabase.from_csv_file("does/not/exist.csv")
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("expected error for missing file")
+
+
+if __name__ == "__main__":
+    for name, test in sorted(globals().items()):
+        if name.startswith("test_"):
+            test()
+    print("ok")
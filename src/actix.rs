@@ -0,0 +1,295 @@
+//! An actix-web extractor for the value associated with the client of a request, and a
+//! middleware that looks up the client of each request.
+//!
+//! ```rust,ignore
+//! let geo = GeoConfig::new(Arc::new(include_country_code_database!()));
+//! HttpServer::new(move || {
+//!     App::new().app_data(geo.clone()).route(
+//!         "/",
+//!         web::get().to(|Geo(country_code): Geo<CountryCode>| async move {
+//!             format!("{:?}", country_code)
+//!         }),
+//!     )
+//! })
+//! ```
+
+pub use crate::client_ip::ClientGeo;
+use crate::client_ip::TrustedProxies;
+use crate::{DbIpDatabase, IpData};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// A shared [`DbIpDatabase`], registered with `App::app_data`, that [`Geo`] looks up clients in.
+///
+/// The client address is determined from the peer address of the connection.
+pub struct GeoConfig<V> {
+    db: Arc<DbIpDatabase<V>>,
+    trusted_proxies: Arc<TrustedProxies>,
+}
+
+impl<V> GeoConfig<V> {
+    pub fn new(db: Arc<DbIpDatabase<V>>) -> Self {
+        Self {
+            db,
+            trusted_proxies: Arc::new(TrustedProxies::new()),
+        }
+    }
+
+    /// Respects forwarding headers set by `trusted_proxies`. See [`TrustedProxies::client_ip`].
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+
+    /// The address of the client that sent `request`, if it could be determined.
+    pub fn client_ip(&self, request: &HttpRequest) -> Option<IpAddr> {
+        let peer = request.peer_addr()?;
+        let headers = request.headers().iter();
+        let headers = headers.map(|(name, value)| (name.as_str(), value.as_bytes()));
+        Some(self.trusted_proxies.client_ip(peer.ip(), headers))
+    }
+}
+
+impl<V: IpData> GeoConfig<V> {
+    /// The value associated with the client that sent `request`, if any.
+    pub fn get(&self, request: &HttpRequest) -> Option<V> {
+        self.lookup(request).value
+    }
+
+    /// Looks up the client that sent `request`.
+    pub fn lookup(&self, request: &HttpRequest) -> ClientGeo<V> {
+        let ip = self.client_ip(request);
+        let value = ip.and_then(|ip| self.db.get(&ip));
+        ClientGeo { ip, value }
+    }
+}
+
+impl<V> Clone for GeoConfig<V> {
+    fn clone(&self) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            trusted_proxies: Arc::clone(&self.trusted_proxies),
+        }
+    }
+}
+
+/// A middleware, registered with `App::wrap`, that looks up the client of each request and
+/// inserts a [`ClientGeo`] into the request extensions (e.g. for `web::ReqData<ClientGeo<V>>`).
+///
+/// ```rust,ignore
+/// App::new()
+///     .wrap(GeoMiddleware::new(geo.clone()))
+///     .route("/", web::get().to(|geo: web::ReqData<ClientGeo<CountryCode>>| async move {
+///         format!("{:?} is in {:?}", geo.ip, geo.value)
+///     }))
+/// ```
+pub struct GeoMiddleware<V>(GeoConfig<V>);
+
+impl<V> GeoMiddleware<V> {
+    pub fn new(config: GeoConfig<V>) -> Self {
+        Self(config)
+    }
+}
+
+impl<V> Clone for GeoMiddleware<V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<S, B, V> Transform<S, ServiceRequest> for GeoMiddleware<V>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    V: IpData + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = GeoMiddlewareService<S, V>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(GeoMiddlewareService {
+            service,
+            config: self.0.clone(),
+        }))
+    }
+}
+
+/// The [`Service`] produced by [`GeoMiddleware`].
+pub struct GeoMiddlewareService<S, V> {
+    service: S,
+    config: GeoConfig<V>,
+}
+
+impl<S, B, V> Service<ServiceRequest> for GeoMiddlewareService<S, V>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    V: IpData + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let geo = self.config.lookup(request.request());
+        request.extensions_mut().insert(geo);
+        self.service.call(request)
+    }
+}
+
+/// Extracts the value associated with the client of a request, such as a
+/// [`CountryCode`](crate::CountryCode) or [`Region`](crate::Region).
+///
+/// Uses the lookup of [`GeoMiddleware`] if present, and otherwise requires a [`GeoConfig`] with
+/// the same value type in the app data. Fails with a 500 Internal Server Error if there is
+/// neither.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Geo<V>(pub Option<V>);
+
+impl<V: IpData + 'static> FromRequest for Geo<V> {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if let Some(geo) = request.extensions().get::<ClientGeo<V>>() {
+            return ready(Ok(Self(geo.value)));
+        }
+        ready(
+            request
+                .app_data::<GeoConfig<V>>()
+                .map(|config| Self(config.get(request)))
+                .ok_or_else(|| ErrorInternalServerError("db_ip::actix::GeoConfig is missing")),
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod test {
+    use super::{ClientGeo, Geo, GeoConfig, GeoMiddleware};
+    use crate::client_ip::{ForwardingHeader, TrustedProxies};
+    use crate::{CountryCode, DbIpDatabase};
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpServer};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+
+    fn config() -> GeoConfig<CountryCode> {
+        let db = DbIpDatabase::from_csv_str(include_str!("../test_country_data.csv")).unwrap();
+        GeoConfig::new(Arc::new(db))
+    }
+
+    async fn handler(Geo(country_code): Geo<CountryCode>) -> String {
+        country_code.map(|cc| cc.to_string()).unwrap_or_default()
+    }
+
+    fn request(peer: &str) -> test::TestRequest {
+        test::TestRequest::get()
+            .uri("/")
+            .peer_addr(peer.parse().unwrap())
+    }
+
+    #[actix_web::test]
+    async fn peer_addr() {
+        let app = test::init_service(
+            App::new()
+                .app_data(config())
+                .route("/", web::get().to(handler)),
+        )
+        .await;
+
+        let body = test::call_and_read_body(&app, request("1.2.3.4:5678").to_request()).await;
+        assert_eq!(body, "AU");
+
+        let body = test::call_and_read_body(&app, request("0.0.0.0:5678").to_request()).await;
+        assert_eq!(body, "");
+    }
+
+    #[actix_web::test]
+    async fn trusted_proxies() {
+        let config =
+            config().trusted_proxies(TrustedProxies::loopback().header(ForwardingHeader::XRealIp));
+        let app = test::init_service(
+            App::new()
+                .app_data(config)
+                .route("/", web::get().to(handler)),
+        )
+        .await;
+
+        let forwarded = request("127.0.0.1:5678").insert_header(("x-real-ip", "1.2.3.4"));
+        let body = test::call_and_read_body(&app, forwarded.to_request()).await;
+        assert_eq!(body, "AU");
+
+        // Not a trusted proxy.
+        let spoofed = request("1.2.3.4:5678").insert_header(("x-real-ip", "0.0.0.7"));
+        let body = test::call_and_read_body(&app, spoofed.to_request()).await;
+        assert_eq!(body, "AU");
+    }
+
+    #[actix_web::test]
+    async fn missing_config() {
+        let app = test::init_service(App::new().route("/", web::get().to(handler))).await;
+        let response = test::call_service(&app, request("1.2.3.4:5678").to_request()).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    /// Sends a request with `headers` to a server over a real connection, from `127.0.0.1`.
+    fn get(listener: &TcpListener, path: &str, headers: &str) -> String {
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\r\n",
+            path, headers
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+        body.to_owned()
+    }
+
+    #[actix_web::test]
+    async fn middleware() {
+        let config = config().trusted_proxies(TrustedProxies::loopback());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = HttpServer::new(move || {
+            App::new()
+                .wrap(GeoMiddleware::new(config.clone()))
+                .route("/", web::get().to(handler))
+                .route(
+                    "/ip",
+                    web::get().to(|geo: web::ReqData<ClientGeo<CountryCode>>| async move {
+                        format!("{:?}", geo.ip)
+                    }),
+                )
+        })
+        .workers(1)
+        .listen(listener.try_clone().unwrap())
+        .unwrap()
+        .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let responses = actix_web::rt::task::spawn_blocking(move || {
+            [
+                get(&listener, "/", "x-forwarded-for: 0.0.0.7\r\n"),
+                get(&listener, "/", ""),
+                get(&listener, "/ip", ""),
+            ]
+        })
+        .await
+        .unwrap();
+        assert_eq!(responses, ["US", "AU", "Some(127.0.0.1)"]);
+
+        handle.stop(true).await;
+    }
+}
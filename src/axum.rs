@@ -0,0 +1,235 @@
+//! A [`tower_layer::Layer`] that looks up the client of each request, and an axum extractor for
+//! the result.
+//!
+//! ```rust,ignore
+//! let db = Arc::new(include_country_code_database!());
+//! let app = Router::new()
+//!     .route("/", get(|Geo(country_code): Geo<CountryCode>| async move {
+//!         format!("{:?}", country_code)
+//!     }))
+//!     .layer(GeoLayer::new(db));
+//! axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+//! ```
+
+pub use crate::client_ip::ClientGeo;
+use crate::client_ip::TrustedProxies;
+use crate::{DbIpDatabase, IpData};
+use ::axum::extract::{ConnectInfo, FromRequestParts};
+use http::request::Parts;
+use http::{Request, StatusCode};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Looks up the client address of each request in a [`DbIpDatabase`], and inserts a
+/// [`ClientGeo`] into the request extensions.
+///
+/// The client address is determined from the peer address from axum's [`ConnectInfo`], so the
+/// app must be served with `into_make_service_with_connect_info::<SocketAddr>()`.
+pub struct GeoLayer<V> {
+    db: Arc<DbIpDatabase<V>>,
+    trusted_proxies: Arc<TrustedProxies>,
+}
+
+impl<V> GeoLayer<V> {
+    pub fn new(db: Arc<DbIpDatabase<V>>) -> Self {
+        Self {
+            db,
+            trusted_proxies: Arc::new(TrustedProxies::new()),
+        }
+    }
+
+    /// Respects forwarding headers set by `trusted_proxies`. See [`TrustedProxies::client_ip`].
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = Arc::new(trusted_proxies);
+        self
+    }
+}
+
+impl<V> Clone for GeoLayer<V> {
+    fn clone(&self) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            trusted_proxies: Arc::clone(&self.trusted_proxies),
+        }
+    }
+}
+
+impl<S, V> Layer<S> for GeoLayer<V> {
+    type Service = GeoService<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GeoService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The [`Service`] produced by [`GeoLayer`].
+pub struct GeoService<S, V> {
+    inner: S,
+    layer: GeoLayer<V>,
+}
+
+impl<S: Clone, V> Clone for GeoService<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+impl<S, V, B> Service<Request<B>> for GeoService<S, V>
+where
+    S: Service<Request<B>>,
+    V: IpData + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let ip = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| {
+                let headers = request.headers().iter();
+                let headers = headers.map(|(name, value)| (name.as_str(), value.as_bytes()));
+                self.layer.trusted_proxies.client_ip(peer.ip(), headers)
+            });
+        let value = ip.and_then(|ip| self.layer.db.get(&ip));
+
+        request.extensions_mut().insert(ClientGeo { ip, value });
+        self.inner.call(request)
+    }
+}
+
+/// Extracts the value associated with the client of a request, such as a
+/// [`CountryCode`](crate::CountryCode) or [`Region`](crate::Region).
+///
+/// Requires [`GeoLayer`] with the same value type, and rejects the request with a 500 Internal
+/// Server Error otherwise.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Geo<V>(pub Option<V>);
+
+impl<S, V> FromRequestParts<S> for Geo<V>
+where
+    S: Send + Sync,
+    V: IpData + Send + Sync + 'static,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientGeo<V>>()
+            .map(|geo| Self(geo.value))
+            .ok_or((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "db_ip::axum::GeoLayer is missing",
+            ))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod test {
+    use super::{ClientGeo, Geo, GeoLayer};
+    use crate::client_ip::TrustedProxies;
+    use crate::{CountryCode, DbIpDatabase};
+    use ::axum::body::Body;
+    use ::axum::extract::ConnectInfo;
+    use ::axum::routing::get;
+    use ::axum::{Extension, Router};
+    use http::{Request, StatusCode};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    fn app(layer: Option<GeoLayer<CountryCode>>) -> Router {
+        let router = Router::new()
+            .route(
+                "/",
+                get(|Geo(country_code): Geo<CountryCode>| async move {
+                    country_code.map(|cc| cc.to_string()).unwrap_or_default()
+                }),
+            )
+            .route(
+                "/ip",
+                get(
+                    |Extension(geo): Extension<ClientGeo<CountryCode>>| async move {
+                        format!("{:?}", geo.ip)
+                    },
+                ),
+            );
+        match layer {
+            Some(layer) => router.layer(layer),
+            None => router,
+        }
+    }
+
+    fn layer() -> GeoLayer<CountryCode> {
+        let db = DbIpDatabase::from_csv_str(include_str!("../test_country_data.csv")).unwrap();
+        GeoLayer::new(Arc::new(db))
+    }
+
+    async fn respond(app: Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = ::axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    fn request(uri: &str, peer: &str) -> Request<Body> {
+        let mut request = Request::get(uri).body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        request
+    }
+
+    #[tokio::test]
+    async fn connect_info() {
+        let (status, body) = respond(app(Some(layer())), request("/", "1.2.3.4:5678")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "AU");
+
+        let (_, body) = respond(app(Some(layer())), request("/", "0.0.0.0:5678")).await;
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn trusted_proxies() {
+        let layer = layer().trusted_proxies(TrustedProxies::loopback());
+
+        let mut forwarded = request("/", "127.0.0.1:5678");
+        forwarded
+            .headers_mut()
+            .insert("x-forwarded-for", "0.0.0.7, 1.2.3.4".parse().unwrap());
+        assert_eq!(respond(app(Some(layer.clone())), forwarded).await.1, "AU");
+
+        // Not a trusted proxy.
+        let mut spoofed = request("/ip", "1.2.3.4:5678");
+        spoofed
+            .headers_mut()
+            .insert("x-forwarded-for", "0.0.0.7".parse().unwrap());
+        let (_, ip) = respond(app(Some(layer)), spoofed).await;
+        assert_eq!(ip, "Some(1.2.3.4)");
+    }
+
+    #[tokio::test]
+    async fn missing_layer() {
+        let (status, _) = respond(app(None), request("/", "1.2.3.4:5678")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,142 @@
+//! Answers lookups over a Unix domain socket. See [`db_ip::daemon`] for the protocol.
+//!
+//! ```text
+//! db_ip_daemon --socket /run/db_ip.sock [--region] [--csv dbip-country-lite.csv.gz]
+//! ```
+//!
+//! Without `--csv`, the embedded database is served. On `SIGHUP`, the database is reloaded.
+
+use db_ip::{CountryCode, DatabaseSource, DbIpDatabase, IpData, Region, SharedDatabase};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::fmt::Display;
+use std::io::{self, BufWriter};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+
+struct Args {
+    socket: PathBuf,
+    region: bool,
+    csv: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut socket = None;
+    let mut region = false;
+    let mut csv = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--socket" => socket = Some(args.next().ok_or("--socket requires a path")?.into()),
+            "--region" => region = true,
+            "--csv" => csv = Some(args.next().ok_or("--csv requires a path")?),
+            _ => return Err(format!("unexpected argument {:?}", arg)),
+        }
+    }
+    Ok(Args {
+        socket: socket.ok_or("--socket is required")?,
+        region,
+        csv,
+    })
+}
+
+fn source<V>(
+    csv: Option<String>,
+    embedded: Option<DatabaseSource<V>>,
+) -> Result<DatabaseSource<V>, String> {
+    csv.map(DatabaseSource::CsvFile)
+        .or(embedded)
+        .ok_or_else(|| "no embedded database, specify --csv".to_owned())
+}
+
+fn load<V: IpData>(source: &DatabaseSource<V>) -> Result<DbIpDatabase<V>, String> {
+    source
+        .load()
+        .map_err(|e| format!("failed to load database: {:?}", e))
+}
+
+/// Removes a socket left by a previous run, which would prevent binding. Anything else at `path`
+/// is left alone.
+fn remove_socket(path: &Path) -> Result<(), String> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)
+            .map_err(|e| format!("failed to remove {}: {}", path.display(), e)),
+        Ok(_) => Err(format!("{} exists and is not a socket", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("failed to inspect {}: {}", path.display(), e)),
+    }
+}
+
+fn serve<V>(args: Args, embedded: Option<DatabaseSource<V>>) -> Result<(), String>
+where
+    V: IpData + Display + Send + Sync + 'static,
+{
+    let source = source(args.csv.clone(), embedded)?;
+    let db = SharedDatabase::new(load(&source)?);
+
+    remove_socket(&args.socket)?;
+    let listener = UnixListener::bind(&args.socket)
+        .map_err(|e| format!("failed to listen on {}: {}", args.socket.display(), e))?;
+    eprintln!("listening on {}", args.socket.display());
+
+    let mut signals = Signals::new([SIGHUP, SIGINT, SIGTERM]).map_err(|e| e.to_string())?;
+    {
+        let db = db.clone();
+        let socket = args.socket.clone();
+        std::thread::spawn(move || {
+            for signal in signals.forever() {
+                if signal != SIGHUP {
+                    if let Err(e) = remove_socket(&socket) {
+                        eprintln!("db_ip_daemon: {}", e);
+                    }
+                    std::process::exit(0);
+                }
+                // A panic would end this thread, and with it the handling of signals.
+                let reloaded = std::panic::catch_unwind(AssertUnwindSafe(|| load(&source)))
+                    .unwrap_or_else(|_| Err("reload panicked".to_owned()));
+                match reloaded {
+                    Ok(reloaded) => {
+                        eprintln!("reloaded {} ranges", reloaded.len());
+                        db.store(reloaded);
+                    }
+                    Err(e) => eprintln!("{}, keeping current database", e),
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let db = db.clone();
+        std::thread::spawn(move || {
+            let reader = match stream.try_clone() {
+                Ok(reader) => reader,
+                Err(e) => return eprintln!("failed to clone connection: {}", e),
+            };
+            if let Err(e) = db_ip::daemon::serve(&db, reader, BufWriter::new(stream)) {
+                eprintln!("connection failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = match parse_args() {
+        Ok(args) if args.region => serve(args, DatabaseSource::<Region>::embedded()),
+        Ok(args) => serve(args, DatabaseSource::<CountryCode>::embedded()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("db_ip_daemon: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,116 @@
+//! Serves JSON lookups over HTTP. See [`db_ip::server`] for the endpoints.
+//!
+//! ```text
+//! db_ip_server [--listen 127.0.0.1:8080] [--region] [--csv dbip-country-lite.csv.gz]
+//! ```
+//!
+//! Without `--csv`, the embedded database is served. On Unix, the database is reloaded on
+//! `SIGHUP`.
+
+use db_ip::{CountryCode, DatabaseSource, DbIpDatabase, IpData, Region, SharedDatabase};
+use std::fmt::Display;
+use std::net::SocketAddr;
+
+struct Args {
+    listen: SocketAddr,
+    region: bool,
+    csv: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut ret = Args {
+        listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+        region: false,
+        csv: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                let listen = args.next().ok_or("--listen requires an address")?;
+                ret.listen = listen
+                    .parse()
+                    .map_err(|e| format!("invalid --listen address: {}", e))?;
+            }
+            "--region" => ret.region = true,
+            "--csv" => ret.csv = Some(args.next().ok_or("--csv requires a path")?),
+            _ => return Err(format!("unexpected argument {:?}", arg)),
+        }
+    }
+    Ok(ret)
+}
+
+fn source<V>(
+    csv: Option<String>,
+    embedded: Option<DatabaseSource<V>>,
+) -> Result<DatabaseSource<V>, String> {
+    csv.map(DatabaseSource::CsvFile)
+        .or(embedded)
+        .ok_or_else(|| "no embedded database, specify --csv".to_owned())
+}
+
+fn load<V: IpData>(source: &DatabaseSource<V>) -> Result<DbIpDatabase<V>, String> {
+    source
+        .load()
+        .map_err(|e| format!("failed to load database: {:?}", e))
+}
+
+async fn serve<V>(args: Args, embedded: Option<DatabaseSource<V>>) -> Result<(), String>
+where
+    V: IpData + Display + Send + Sync + 'static,
+{
+    let source = source(args.csv.clone(), embedded)?;
+    let db = SharedDatabase::new(load(&source)?);
+
+    #[cfg(unix)]
+    reload_on_hangup(db.clone(), source)?;
+
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .map_err(|e| format!("failed to listen on {}: {}", args.listen, e))?;
+    eprintln!("listening on {}", args.listen);
+    axum::serve(listener, db_ip::server::router(db))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reloads the database from `source` on each `SIGHUP`.
+#[cfg(unix)]
+fn reload_on_hangup<V>(db: SharedDatabase<V>, source: DatabaseSource<V>) -> Result<(), String>
+where
+    V: IpData + Send + Sync + 'static,
+{
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let source = std::sync::Arc::new(source);
+    let mut hangup = signal(SignalKind::hangup()).map_err(|e| e.to_string())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            let source = std::sync::Arc::clone(&source);
+            let reloaded = tokio::task::spawn_blocking(move || load(&source))
+                .await
+                .unwrap_or_else(|e| Err(format!("reload failed: {}", e)));
+            match reloaded {
+                Ok(reloaded) => {
+                    eprintln!("reloaded {} ranges", reloaded.len());
+                    db.store(reloaded);
+                }
+                Err(e) => eprintln!("{}, keeping current database", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let result = match parse_args() {
+        Ok(args) if args.region => serve(args, DatabaseSource::<Region>::embedded()).await,
+        Ok(args) => serve(args, DatabaseSource::<CountryCode>::embedded()).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("db_ip_server: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,447 @@
+//! Determining the address of the client that sent a request, which may have passed through
+//! reverse proxies.
+//!
+//! ```
+//! use db_ip::client_ip::{ForwardingHeader, TrustedProxies};
+//!
+//! let proxies = TrustedProxies::from_cidrs(&["10.0.0.0/8", "::1"])
+//!     .unwrap()
+//!     .header(ForwardingHeader::XForwardedFor);
+//! let headers = [("X-Forwarded-For", "1.2.3.4, 6.6.6.6, 10.0.0.2".as_bytes())];
+//! assert_eq!(
+//!     proxies.client_ip("10.0.0.1".parse().unwrap(), headers),
+//!     "6.6.6.6".parse::<std::net::IpAddr>().unwrap()
+//! );
+//! ```
+
+use std::net::{AddrParseError, IpAddr, SocketAddr};
+use std::str::FromStr;
+
+/// A set of networks whose forwarding headers are trusted, and which header they set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+    header: ForwardingHeader,
+}
+
+/// The result of looking up the client of a request, inserted into its extensions by
+/// `db_ip::axum::GeoLayer` and `db_ip::actix::GeoMiddleware`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClientGeo<V> {
+    /// The client address, if it could be determined.
+    pub ip: Option<IpAddr>,
+    /// The value associated with the client address, if any.
+    pub value: Option<V>,
+}
+
+/// A header that reverse proxies use to report the address of the client.
+///
+/// Only the one header that the trusted proxies set can be used. Any other is passed through
+/// from the client, which may put any address in it.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ForwardingHeader {
+    /// `Forwarded` (RFC 7239).
+    Forwarded,
+    /// `X-Forwarded-For`.
+    #[default]
+    XForwardedFor,
+    /// `X-Real-IP`, which holds a single address rather than a list.
+    XRealIp,
+}
+
+impl ForwardingHeader {
+    /// The name of the header, e.g. `"X-Forwarded-For"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Forwarded => "Forwarded",
+            Self::XForwardedFor => "X-Forwarded-For",
+            Self::XRealIp => "X-Real-IP",
+        }
+    }
+}
+
+/// An error parsing a network in CIDR notation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseCidrError {
+    /// Failed to parse the address.
+    AddrParse(AddrParseError),
+    /// The prefix length was not a number, or was too long for the address family.
+    InvalidPrefix,
+}
+
+impl TrustedProxies {
+    /// Trusts no proxies, so [`TrustedProxies::client_ip`] always returns the peer address.
+    ///
+    /// Trusted proxies are assumed to set [`ForwardingHeader::XForwardedFor`] unless
+    /// [`TrustedProxies::header`] says otherwise.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts each network, in CIDR notation (e.g. `10.0.0.0/8`), or each address.
+    pub fn from_cidrs(cidrs: &[&str]) -> Result<Self, ParseCidrError> {
+        let mut ret = Self::new();
+        for cidr in cidrs {
+            ret.push_cidr(cidr)?;
+        }
+        Ok(ret)
+    }
+
+    /// Trusts the loopback networks `127.0.0.0/8` and `::1/128`, for a reverse proxy on the same
+    /// host.
+    pub fn loopback() -> Self {
+        Self::from_cidrs(&["127.0.0.0/8", "::1"]).unwrap()
+    }
+
+    /// Uses the `header` that the trusted proxies set, ignoring any other forwarding header.
+    pub fn header(mut self, header: ForwardingHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Additionally trusts a network, in CIDR notation (e.g. `10.0.0.0/8`), or an address.
+    pub fn push_cidr(&mut self, cidr: &str) -> Result<(), ParseCidrError> {
+        let (addr, prefix) = match cidr.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (cidr, None),
+        };
+        let addr = IpAddr::from_str(addr.trim()).map_err(ParseCidrError::AddrParse)?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => {
+                u8::from_str(prefix.trim()).map_err(|_| ParseCidrError::InvalidPrefix)?
+            }
+            None => max,
+        };
+        if prefix > max {
+            return Err(ParseCidrError::InvalidPrefix);
+        }
+        self.networks.push((addr, prefix));
+        Ok(())
+    }
+
+    /// Whether `ip` belongs to one of the trusted networks. Ipv4-mapped Ipv6 addresses are
+    /// treated as Ipv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.networks
+            .iter()
+            .any(|&(network, prefix)| match (network, ip) {
+                (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                    u32::from(network) & mask == u32::from(ip) & mask
+                }
+                (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                    let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                    u128::from(network) & mask == u128::from(ip) & mask
+                }
+                _ => false,
+            })
+    }
+
+    /// Determines the address of the client, given the `peer` address of the connection and the
+    /// request headers as `(name, value)` pairs (names are case-insensitive).
+    ///
+    /// The forwarding header (see [`TrustedProxies::header`]) is only consulted if `peer` is
+    /// trusted. Its addresses are walked from right to left, skipping trusted proxies, and the
+    /// first untrusted one is the client. Addresses further left could have been chosen by the
+    /// client, so they are never used. If an address can't be parsed (e.g. `for=unknown`), the
+    /// nearest proxy is returned instead. `X-Real-IP` holds a single address, so a list (or a
+    /// repeated header) is treated as unparseable.
+    pub fn client_ip<'a>(
+        &self,
+        peer: IpAddr,
+        headers: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        // Repeated headers are equivalent to a single comma separated header.
+        let mut chain = String::new();
+        for (name, value) in headers {
+            if !name.eq_ignore_ascii_case(self.header.name()) {
+                continue;
+            }
+            let value = match std::str::from_utf8(value) {
+                Ok(value) => value,
+                // Can't trust the rest of the chain.
+                Err(_) => return peer,
+            };
+            if !chain.is_empty() {
+                chain.push(',');
+            }
+            chain.push_str(value);
+        }
+        if chain.is_empty() {
+            return peer;
+        }
+
+        let hops: Vec<Option<IpAddr>> = match self.header {
+            ForwardingHeader::Forwarded => split_quoted(&chain, ',')
+                .map(|element| {
+                    split_quoted(element, ';')
+                        .filter_map(|pair| pair.split_once('='))
+                        .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                        .and_then(|(_, value)| parse_node(value))
+                })
+                .collect(),
+            ForwardingHeader::XForwardedFor => chain.split(',').map(parse_node).collect(),
+            ForwardingHeader::XRealIp if chain.contains(',') => vec![None],
+            ForwardingHeader::XRealIp => vec![parse_node(&chain)],
+        };
+
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            match hop {
+                Some(hop) => {
+                    client = hop.to_canonical();
+                    if !self.contains(client) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        client
+    }
+}
+
+/// Splits on `separator`, except within double quoted strings.
+fn split_quoted(s: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+    s.split(move |c: char| {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && c == separator {
+            return true;
+        }
+        false
+    })
+}
+
+/// Parses an address that may be quoted, bracketed, and/or have a port, such as
+/// `"[2001:db8::1]:4711"`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    IpAddr::from_str(node)
+        .or_else(|_| SocketAddr::from_str(node).map(|addr| addr.ip()))
+        .ok()
+        .or_else(|| {
+            let node = node.strip_prefix('[')?.strip_suffix(']')?;
+            IpAddr::from_str(node).ok()
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_node, ForwardingHeader, ParseCidrError, TrustedProxies};
+    use std::net::IpAddr;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn proxies() -> TrustedProxies {
+        TrustedProxies::from_cidrs(&["10.0.0.0/8", "fd00::/8", "192.0.2.1"]).unwrap()
+    }
+
+    #[test]
+    fn cidrs() {
+        let proxies = proxies();
+        assert!(proxies.contains(ip("10.255.0.1")));
+        assert!(proxies.contains(ip("::ffff:10.0.0.1")));
+        assert!(proxies.contains(ip("fd12::1")));
+        assert!(proxies.contains(ip("192.0.2.1")));
+        assert!(!proxies.contains(ip("192.0.2.2")));
+        assert!(!proxies.contains(ip("11.0.0.1")));
+        assert!(!proxies.contains(ip("fe00::1")));
+        assert!(TrustedProxies::from_cidrs(&["0.0.0.0/0"])
+            .unwrap()
+            .contains(ip("1.2.3.4")));
+
+        assert_eq!(
+            TrustedProxies::from_cidrs(&["10.0.0.0/33"]),
+            Err(ParseCidrError::InvalidPrefix)
+        );
+        assert!(TrustedProxies::from_cidrs(&["10.0.0/8"]).is_err());
+    }
+
+    #[test]
+    fn node() {
+        assert_eq!(parse_node(" 1.2.3.4"), Some(ip("1.2.3.4")));
+        assert_eq!(parse_node("\"1.2.3.4:80\""), Some(ip("1.2.3.4")));
+        assert_eq!(
+            parse_node("\"[2001:db8::1]:4711\""),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(parse_node("[2001:db8::1]"), Some(ip("2001:db8::1")));
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+    }
+
+    fn resolve(proxies: &TrustedProxies, peer: &str, headers: &[(&str, &str)]) -> IpAddr {
+        proxies.client_ip(ip(peer), headers.iter().map(|&(k, v)| (k, v.as_bytes())))
+    }
+
+    #[test]
+    fn headers() {
+        let proxies = proxies();
+        let client_ip = |peer: &str, headers: &[(&str, &str)]| resolve(&proxies, peer, headers);
+
+        assert_eq!(client_ip("10.0.0.1", &[]), ip("10.0.0.1"));
+        assert_eq!(
+            client_ip("10.0.0.1", &[("X-Forwarded-For", "1.2.3.4")]),
+            ip("1.2.3.4")
+        );
+        let real_ip = proxies.clone().header(ForwardingHeader::XRealIp);
+        assert_eq!(
+            resolve(&real_ip, "10.0.0.1", &[("x-real-ip", "2001:db8::1")]),
+            ip("2001:db8::1")
+        );
+        // X-Real-IP is a single address, not a list.
+        assert_eq!(
+            resolve(&real_ip, "10.0.0.1", &[("X-Real-IP", "1.2.3.4, 5.6.7.8")]),
+            ip("10.0.0.1")
+        );
+        assert_eq!(
+            resolve(
+                &real_ip,
+                "10.0.0.1",
+                &[("X-Real-IP", "1.2.3.4"), ("X-Real-IP", "5.6.7.8")]
+            ),
+            ip("10.0.0.1")
+        );
+        // Repeated headers, and chains of trusted proxies.
+        assert_eq!(
+            client_ip(
+                "10.0.0.1",
+                &[
+                    ("x-forwarded-for", "1.2.3.4, 5.6.7.8"),
+                    ("x-forwarded-for", "10.0.0.3, 10.0.0.2")
+                ]
+            ),
+            ip("5.6.7.8")
+        );
+        // All proxies trusted.
+        assert_eq!(
+            client_ip("10.0.0.1", &[("x-forwarded-for", "10.0.0.3,10.0.0.2")]),
+            ip("10.0.0.3")
+        );
+        // Other headers are ignored.
+        let forwarded = proxies.clone().header(ForwardingHeader::Forwarded);
+        assert_eq!(
+            resolve(
+                &forwarded,
+                "10.0.0.1",
+                &[
+                    ("X-Forwarded-For", "5.6.7.8"),
+                    (
+                        "Forwarded",
+                        "for=1.2.3.4;proto=https, For=\"[2001:db8::1]:4711\";by=\"a,b\""
+                    ),
+                ]
+            ),
+            ip("2001:db8::1")
+        );
+    }
+
+    #[test]
+    fn spoofing() {
+        let proxies = proxies();
+        let client_ip = |peer: &str, headers: &[(&str, &str)]| resolve(&proxies, peer, headers);
+
+        // Untrusted peers can't forward.
+        assert_eq!(
+            client_ip("6.6.6.6", &[("x-forwarded-for", "1.2.3.4")]),
+            ip("6.6.6.6")
+        );
+        assert_eq!(
+            client_ip("6.6.6.6", &[("forwarded", "for=10.0.0.2")]),
+            ip("6.6.6.6")
+        );
+        // The client prepended addresses, including a trusted one.
+        assert_eq!(
+            client_ip(
+                "10.0.0.1",
+                &[("x-forwarded-for", "1.2.3.4, 10.0.0.5, 6.6.6.6")]
+            ),
+            ip("6.6.6.6")
+        );
+        // The client sent an unparsable address, so use the proxy that reported it.
+        assert_eq!(
+            client_ip(
+                "10.0.0.1",
+                &[("x-forwarded-for", "1.2.3.4, garbage, 10.0.0.2")]
+            ),
+            ip("10.0.0.2")
+        );
+        assert_eq!(
+            client_ip("10.0.0.1", &[("x-forwarded-for", "unknown, 10.0.0.2")]),
+            ip("10.0.0.2")
+        );
+        let forwarded = proxies.clone().header(ForwardingHeader::Forwarded);
+        assert_eq!(
+            resolve(&forwarded, "10.0.0.1", &[("forwarded", "for=unknown")]),
+            ip("10.0.0.1")
+        );
+        // A mapped address is no different.
+        assert_eq!(
+            client_ip("::ffff:6.6.6.6", &[("x-forwarded-for", "1.2.3.4")]),
+            ip("6.6.6.6")
+        );
+        // The client tried to smuggle a separator in a quoted string.
+        assert_eq!(
+            resolve(
+                &forwarded,
+                "10.0.0.1",
+                &[("forwarded", "for=1.2.3.4, for=\"6.6.6.6;for=10.0.0.2\"")]
+            ),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn injected_headers() {
+        // The trusted proxy sets one header, and the client injects the others.
+        let injected = |header: ForwardingHeader| match header {
+            ForwardingHeader::Forwarded => ("forwarded", "for=6.6.6.6"),
+            ForwardingHeader::XForwardedFor => ("x-forwarded-for", "6.6.6.6"),
+            ForwardingHeader::XRealIp => ("x-real-ip", "6.6.6.6"),
+        };
+        let headers = [
+            ForwardingHeader::Forwarded,
+            ForwardingHeader::XForwardedFor,
+            ForwardingHeader::XRealIp,
+        ];
+        for header in headers {
+            let proxies = proxies().header(header);
+            let set = match header {
+                ForwardingHeader::Forwarded => ("Forwarded", "for=1.2.3.4"),
+                ForwardingHeader::XForwardedFor => ("X-Forwarded-For", "1.2.3.4"),
+                ForwardingHeader::XRealIp => ("X-Real-IP", "1.2.3.4"),
+            };
+            for other in headers.iter().copied().filter(|&other| other != header) {
+                for request in [[injected(other), set], [set, injected(other)]] {
+                    assert_eq!(
+                        resolve(&proxies, "10.0.0.1", &request),
+                        ip("1.2.3.4"),
+                        "{:?}",
+                        request
+                    );
+                }
+                // The proxy didn't set its header.
+                assert_eq!(
+                    resolve(&proxies, "10.0.0.1", &[injected(other)]),
+                    ip("10.0.0.1")
+                );
+            }
+        }
+    }
+}
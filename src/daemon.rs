@@ -0,0 +1,155 @@
+//! A line protocol for lookups, as served over a Unix domain socket by the `db_ip_daemon` binary.
+//!
+//! Each query is an address on its own line, and each response is the value associated with it
+//! (e.g. `AU` or `Oceania`) on its own line, or `-` if there is no value or the address is invalid.
+//! Lines longer than [`MAX_QUERY_LEN`] bytes, or that aren't UTF-8, are invalid. Responses are in
+//! the same order as queries, so many queries may be sent before reading any responses.
+
+use crate::{IpData, SharedDatabase};
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// The maximum length of a query, including the newline. Longer lines are answered with `-`
+/// without being buffered.
+pub const MAX_QUERY_LEN: usize = 256;
+
+/// Answers queries from `reader` until it is exhausted, writing responses to `writer`, which
+/// should be buffered.
+///
+/// Responses are flushed whenever no more queries are buffered, so pipelined queries are answered
+/// in batches. Each batch is answered from the same database, even if it is reloaded meanwhile.
+pub fn serve<V, R, W>(db: &SharedDatabase<V>, reader: R, mut writer: W) -> io::Result<()>
+where
+    V: IpData + Display,
+    R: Read,
+    W: Write,
+{
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::with_capacity(MAX_QUERY_LEN);
+    let mut current = db.load();
+
+    loop {
+        line.clear();
+        let len = (&mut reader)
+            .take(MAX_QUERY_LEN as u64)
+            .read_until(b'\n', &mut line)?;
+        if len == 0 {
+            return writer.flush();
+        }
+        if len == MAX_QUERY_LEN && line.last() != Some(&b'\n') {
+            skip_line(&mut reader)?;
+            writer.write_all(b"-\n")?;
+        } else {
+            match std::str::from_utf8(&line) {
+                Ok(query) => respond(&current, query, &mut writer)?,
+                Err(_) => writer.write_all(b"-\n")?,
+            }
+        }
+
+        if reader.buffer().is_empty() {
+            writer.flush()?;
+            current = db.load();
+        }
+    }
+}
+
+/// Writes the response to a single query, including the newline.
+pub fn respond<V: IpData + Display>(
+    db: &crate::DbIpDatabase<V>,
+    query: &str,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    match IpAddr::from_str(query.trim())
+        .ok()
+        .and_then(|ip| db.get(&ip))
+    {
+        Some(value) => writeln!(writer, "{}", value),
+        None => writer.write_all(b"-\n"),
+    }
+}
+
+/// Discards the rest of the current line, including the newline.
+fn skip_line(reader: &mut impl BufRead) -> io::Result<()> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        match buffer.iter().position(|&b| b == b'\n') {
+            Some(newline) => {
+                reader.consume(newline + 1);
+                return Ok(());
+            }
+            None if buffer.is_empty() => return Ok(()),
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "csv")]
+mod test {
+    use super::{serve, MAX_QUERY_LEN};
+    use crate::{CountryCode, DbIpDatabase, SharedDatabase};
+    use std::io::{BufRead, BufReader, Write};
+
+    fn db() -> SharedDatabase<CountryCode> {
+        SharedDatabase::new(
+            DbIpDatabase::from_csv_str(include_str!("../test_country_data.csv")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn pipelined() {
+        let db = db();
+        let queries = "1.2.3.4\n0.0.0.0\r\nnope\n\n 2a07:7ec5:8233::1 \n0.0.0.7";
+        let mut responses = Vec::new();
+        serve(&db, queries.as_bytes(), &mut responses).unwrap();
+        assert_eq!(
+            String::from_utf8(responses).unwrap(),
+            "AU\n-\n-\n-\nAU\nUS\n"
+        );
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let db = db();
+        let mut queries = b"1.2.3.4\n\xff\n".to_vec();
+        queries.resize(queries.len() + MAX_QUERY_LEN * 100, b' ');
+        queries.extend_from_slice(b"1.2.3.4\n2a07:7ec5:8233::1\n");
+        let mut responses = Vec::new();
+        serve(&db, queries.as_slice(), &mut responses).unwrap();
+        assert_eq!(String::from_utf8(responses).unwrap(), "AU\n-\n-\nAU\n");
+
+        let mut responses = Vec::new();
+        let longest = format!("{:>1$}\n", "1.2.3.4", MAX_QUERY_LEN - 1);
+        serve(&db, longest.as_bytes(), &mut responses).unwrap();
+        assert_eq!(responses, b"AU\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn interactive() {
+        let (mut client, server) = std::os::unix::net::UnixStream::pair().unwrap();
+        let db = db();
+        let thread = std::thread::spawn(move || {
+            let writer = server.try_clone().unwrap();
+            serve(&db, server, std::io::BufWriter::new(writer))
+        });
+
+        // Responses must arrive while the connection is still open.
+        let mut responses = BufReader::new(client.try_clone().unwrap());
+        let mut response = String::new();
+        for (query, expected) in [("1.2.3.4\n", "AU\n"), ("::1\n", "-\n")] {
+            client.write_all(query.as_bytes()).unwrap();
+            response.clear();
+            responses.read_line(&mut response).unwrap();
+            assert_eq!(response, expected);
+        }
+
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        thread.join().unwrap().unwrap();
+    }
+}
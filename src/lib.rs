@@ -3,9 +3,22 @@
 
 pub use db_ip_core::*;
 
-#[cfg(feature = "bincode")]
-#[doc(hidden)]
-pub use bincode;
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub mod client_ip;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+mod shared;
+
+#[cfg(all(feature = "csv", any(feature = "ipv4", feature = "ipv6")))]
+pub use shared::DatabaseSource;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+pub use shared::SharedDatabase;
 
 #[cfg(feature = "include-region-lite")]
 #[doc(hidden)]
@@ -16,24 +29,158 @@ pub const REGION_LITE_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/r
 pub const COUNTRY_CODE_LITE_BYTES: &[u8] =
     include_bytes!(concat!(env!("OUT_DIR"), "/country_code_lite.bin"));
 
+/// Describes the embedded lite databases, which [`include_country_code_database`] and
+/// [`include_region_database`] attach to the databases they load.
+#[cfg(any(feature = "include-country-code-lite", feature = "include-region-lite"))]
+pub const LITE_METADATA: DatabaseMetadata = include!(concat!(env!("OUT_DIR"), "/lite_metadata.rs"));
+
+#[cfg(all(feature = "static-lite", feature = "include-country-code-lite"))]
+static COUNTRY_CODE_LITE: DbIpDatabaseRef<'static, CountryCode> =
+    include!(concat!(env!("OUT_DIR"), "/country_code_lite.rs"));
+
+#[cfg(all(feature = "static-lite", feature = "include-region-lite"))]
+static REGION_LITE: DbIpDatabaseRef<'static, Region> =
+    include!(concat!(env!("OUT_DIR"), "/region_lite.rs"));
+
+/// The embedded country code lite database, like [`include_country_code_database`], but
+/// generated as `static` data at build time, so it costs no deserialization or heap allocation.
+///
+/// Its metadata is [`LITE_METADATA`].
+#[cfg(all(feature = "static-lite", feature = "include-country-code-lite"))]
+pub fn global_country_database() -> DbIpDatabaseRef<'static, CountryCode> {
+    COUNTRY_CODE_LITE
+}
+
+/// The embedded region lite database, like [`include_region_database`], but generated as
+/// `static` data at build time, so it costs no deserialization or heap allocation.
+///
+/// Its metadata is [`LITE_METADATA`].
+#[cfg(all(feature = "static-lite", feature = "include-region-lite"))]
+pub fn global_region_database() -> DbIpDatabaseRef<'static, Region> {
+    REGION_LITE
+}
+
+/// The embedded country code lite database, decompressed once, the first time this is called.
+///
+/// Its metadata is [`LITE_METADATA`].
+#[cfg(all(
+    feature = "compressed-lite",
+    not(feature = "static-lite"),
+    feature = "include-country-code-lite"
+))]
+pub fn global_country_database() -> DbIpDatabaseRef<'static, CountryCode> {
+    static COUNTRY_CODE_LITE: std::sync::OnceLock<DbIpDatabase<CountryCode>> =
+        std::sync::OnceLock::new();
+    COUNTRY_CODE_LITE
+        .get_or_init(|| include_country_code_database!())
+        .as_database_ref()
+}
+
+/// The embedded region lite database, decompressed once, the first time this is called.
+///
+/// Its metadata is [`LITE_METADATA`].
+#[cfg(all(
+    feature = "compressed-lite",
+    not(feature = "static-lite"),
+    feature = "include-region-lite"
+))]
+pub fn global_region_database() -> DbIpDatabaseRef<'static, Region> {
+    static REGION_LITE: std::sync::OnceLock<DbIpDatabase<Region>> = std::sync::OnceLock::new();
+    REGION_LITE
+        .get_or_init(|| include_region_database!())
+        .as_database_ref()
+}
+
+/// Loads the embedded region lite database.
+///
+/// Each call deserializes the database again (and with the `compressed-lite` feature, decompresses
+/// it), so prefer calling it once and sharing the result.
+///
+/// # Panics
+///
+/// If the embedded database is invalid, see [`try_include_region_database`].
 #[macro_export]
 #[cfg(feature = "include-region-lite")]
 macro_rules! include_region_database {
-    () => {{
-        let db: $crate::DbIpDatabase<$crate::Region> =
-            $crate::bincode::deserialize($crate::REGION_LITE_BYTES).unwrap();
-        db
-    }};
+    () => {
+        $crate::try_include_region_database!().unwrap()
+    };
 }
 
+/// Loads the embedded region lite database, or returns a [`FromBytesError`] if it is invalid.
+#[macro_export]
+#[cfg(feature = "include-region-lite")]
+macro_rules! try_include_region_database {
+    () => {
+        $crate::DbIpDatabase::<$crate::Region>::from_bytes($crate::REGION_LITE_BYTES)
+            .map(|db| db.with_metadata($crate::LITE_METADATA))
+    };
+}
+
+/// Loads the embedded country code lite database.
+///
+/// Each call deserializes the database again (and with the `compressed-lite` feature, decompresses
+/// it), so prefer calling it once and sharing the result.
+///
+/// # Panics
+///
+/// If the embedded database is invalid, see [`try_include_country_code_database`].
 #[macro_export]
 #[cfg(feature = "include-country-code-lite")]
 macro_rules! include_country_code_database {
-    () => {{
-        let db: $crate::DbIpDatabase<$crate::CountryCode> =
-            $crate::bincode::deserialize($crate::COUNTRY_CODE_LITE_BYTES).unwrap();
-        db
-    }};
+    () => {
+        $crate::try_include_country_code_database!().unwrap()
+    };
+}
+
+/// Loads the embedded country code lite database, or returns a [`FromBytesError`] if it is
+/// invalid.
+#[macro_export]
+#[cfg(feature = "include-country-code-lite")]
+macro_rules! try_include_country_code_database {
+    () => {
+        $crate::DbIpDatabase::<$crate::CountryCode>::from_bytes($crate::COUNTRY_CODE_LITE_BYTES)
+            .map(|db| db.with_metadata($crate::LITE_METADATA))
+    };
+}
+
+#[cfg(all(feature = "include-csv", any(feature = "ipv4", feature = "ipv6")))]
+#[doc(hidden)]
+pub use db_ip_csv_macros::database_bytes_from_csv;
+
+/// Embeds a database loaded from a CSV file in the format of db-ip.com's country or city data,
+/// such as your own address ranges or a paid db-ip.com database. The file is read, validated and
+/// serialized at compile time, relative to the crate's `Cargo.toml`, so no build script is needed.
+///
+/// The value type must be [`CountryCode`] or [`Region`], named as such (e.g. not through a type
+/// alias). Other types, including your own types of the same name, fail to compile, so use
+/// `DbIpDatabase::from_csv_str(include_str!(..))` for them instead.
+///
+/// ```rust,ignore
+/// let db = include_database_from_csv!(CountryCode, "data/our_ranges.csv");
+/// ```
+///
+/// # Panics
+///
+/// If the embedded database is invalid, see [`try_include_database_from_csv`].
+#[macro_export]
+#[cfg(all(feature = "include-csv", any(feature = "ipv4", feature = "ipv6")))]
+macro_rules! include_database_from_csv {
+    ($value:ty, $path:literal) => {
+        $crate::try_include_database_from_csv!($value, $path).unwrap()
+    };
+}
+
+/// Embeds a database loaded from a CSV file like [`include_database_from_csv`], or returns a
+/// [`FromBytesError`] if it is invalid.
+#[macro_export]
+#[cfg(all(feature = "include-csv", any(feature = "ipv4", feature = "ipv6")))]
+macro_rules! try_include_database_from_csv {
+    ($value:ty, $path:literal) => {
+        $crate::DbIpDatabase::<$value>::from_bytes($crate::database_bytes_from_csv!(
+            $crate, $value, $path
+        ))
+    };
 }
 
 #[cfg(test)]
@@ -49,6 +196,63 @@ mod test {
         assert_eq!(CountryCode::from_str("US"), CountryCode::from_str("us"));
     }
 
+    #[test]
+    #[cfg(all(feature = "include-csv", feature = "csv"))]
+    fn include_database_from_csv() {
+        let csv = include_str!("../test_country_data.csv");
+        let db = include_database_from_csv!(CountryCode, "test_country_data.csv");
+        let expected = DbIpDatabase::<CountryCode>::from_csv_str(csv).unwrap();
+        assert!(db.iter().eq(expected.iter()));
+        assert_eq!(db.len(), expected.len());
+
+        #[cfg(feature = "region")]
+        {
+            use crate::Region;
+
+            let db = include_database_from_csv!(Region, "test_country_data.csv");
+            let expected = DbIpDatabase::<Region>::from_csv_str(csv).unwrap();
+            assert!(db.iter().eq(expected.iter()));
+            assert_eq!(db.len(), expected.len());
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        any(feature = "static-lite", feature = "compressed-lite"),
+        feature = "include-country-code-lite"
+    ))]
+    fn global_country_database() {
+        let db = include_country_code_database!();
+        let global = crate::global_country_database();
+        assert!(global.iter().eq(db.iter()));
+        assert_eq!(global.len(), db.len());
+    }
+
+    #[test]
+    #[cfg(all(
+        any(feature = "static-lite", feature = "compressed-lite"),
+        feature = "include-region-lite"
+    ))]
+    fn global_region_database() {
+        let db = include_region_database!();
+        let global = crate::global_region_database();
+        assert!(global.iter().eq(db.iter()));
+        assert_eq!(global.len(), db.len());
+    }
+
+    #[test]
+    #[cfg(all(feature = "compressed-lite", feature = "include-country-code-lite"))]
+    fn compressed_lite() {
+        assert_eq!(&crate::COUNTRY_CODE_LITE_BYTES[..4], b"DBIZ");
+
+        assert!(
+            crate::COUNTRY_CODE_LITE_BYTES.len()
+                < include_country_code_database!().to_bytes().len()
+        );
+        #[cfg(feature = "include-region-lite")]
+        assert!(crate::REGION_LITE_BYTES.len() < include_region_database!().to_bytes().len());
+    }
+
     #[test]
     #[cfg(all(feature = "ipv4", feature = "include-region-lite"))]
     fn region_v4() {
@@ -72,6 +276,27 @@ mod test {
         );
     }
 
+    #[test]
+    #[cfg(all(feature = "include-country-code-lite", feature = "include-region-lite"))]
+    fn try_include() {
+        assert!(try_include_country_code_database!().is_ok());
+        assert!(try_include_region_database!().is_ok());
+        assert!(matches!(
+            DbIpDatabase::<crate::Region>::from_bytes(crate::COUNTRY_CODE_LITE_BYTES),
+            Err(crate::FromBytesError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "ipv4", feature = "include-country-code-lite"))]
+    fn lite_metadata() {
+        let db = include_country_code_database!();
+        let metadata = db.metadata();
+        assert_eq!(metadata.attribution.as_deref(), Some(crate::ATTRIBUTION));
+        assert!(metadata.data_month.is_some());
+        assert_eq!(metadata.ranges_v4, db.len_v4());
+    }
+
     #[test]
     #[cfg(all(feature = "ipv4", feature = "include-country-code-lite"))]
     fn city_country_code_v4() {
@@ -0,0 +1,235 @@
+//! JSON lookup endpoints, as served by the `db_ip_server` binary.
+//!
+//! - `GET /lookup/{ip}` looks up a single address.
+//! - `POST /lookup` looks up a JSON array of addresses.
+//! - `GET /cidr/{network}/{prefix}` lists the ranges that overlap a network.
+//! - `GET /health` reports the number of ranges loaded, and the
+//!   [metadata](crate::DbIpDatabase::metadata) of the data, such as its month.
+//!
+//! Each result is an object with `ip`, `value` (`null` if unknown) and the `start` and `end` of
+//! the range the value applies to.
+
+use crate::{DbIpDatabase, IpData, SharedDatabase};
+use ::axum::extract::{Path, State};
+use ::axum::routing::get;
+use ::axum::{Json, Router};
+use http::StatusCode;
+use serde_json::{json, Value};
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// The maximum number of addresses in a batch lookup.
+pub const MAX_BATCH: usize = 10_000;
+
+type JsonResult = Result<Json<Value>, (StatusCode, Json<Value>)>;
+
+/// Creates a [`Router`] serving lookups from `db`.
+pub fn router<V>(db: SharedDatabase<V>) -> Router
+where
+    V: IpData + Display + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/lookup/{ip}", get(lookup::<V>))
+        .route("/lookup", ::axum::routing::post(batch::<V>))
+        .route("/cidr/{*cidr}", get(cidr::<V>))
+        .route("/health", get(health::<V>))
+        .with_state(db)
+}
+
+async fn lookup<V: IpData + Display>(
+    State(db): State<SharedDatabase<V>>,
+    Path(ip): Path<String>,
+) -> JsonResult {
+    let ip = IpAddr::from_str(&ip).map_err(|_| bad_request("invalid address"))?;
+    Ok(Json(result(&db.load(), ip)))
+}
+
+async fn batch<V: IpData + Display>(
+    State(db): State<SharedDatabase<V>>,
+    Json(ips): Json<Vec<String>>,
+) -> JsonResult {
+    if ips.len() > MAX_BATCH {
+        return Err(bad_request("too many addresses"));
+    }
+    let db = db.load();
+    let results = ips
+        .iter()
+        .map(|ip| match IpAddr::from_str(ip) {
+            Ok(ip) => result(&db, ip),
+            Err(_) => json!({"ip": ip, "error": "invalid address"}),
+        })
+        .collect();
+    Ok(Json(Value::Array(results)))
+}
+
+async fn cidr<V: IpData + Display>(
+    State(db): State<SharedDatabase<V>>,
+    Path(cidr): Path<String>,
+) -> JsonResult {
+    let network = parse_cidr(&cidr).ok_or_else(|| bad_request("invalid network"))?;
+    let db = db.load();
+    let ranges = db
+        .iter_from(network.start())
+        .take_while(|(range, _)| range.start() <= network.end())
+        .map(|(range, value)| {
+            json!({
+                "start": range.start(),
+                "end": range.end(),
+                "value": value.to_string(),
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "start": network.start(),
+        "end": network.end(),
+        "ranges": Value::Array(ranges),
+    })))
+}
+
+async fn health<V: IpData>(State(db): State<SharedDatabase<V>>) -> Json<Value> {
+    let metadata = db.load().metadata();
+    Json(json!({
+        "status": "ok",
+        "ranges": metadata.ranges_v4 + metadata.ranges_v6,
+        "ranges_v4": metadata.ranges_v4,
+        "ranges_v6": metadata.ranges_v6,
+        "data_month": metadata
+            .data_month
+            .map(|(year, month)| format!("{}-{:02}", year, month)),
+        "source_url": metadata.source_url,
+        "built_at": metadata.built_at,
+        "attribution": metadata.attribution,
+    }))
+}
+
+fn result<V: IpData + Display>(db: &DbIpDatabase<V>, ip: IpAddr) -> Value {
+    match db.get_range(&ip) {
+        Some((range, value)) => json!({
+            "ip": ip,
+            "value": value.to_string(),
+            "start": range.start(),
+            "end": range.end(),
+        }),
+        None => json!({"ip": ip, "value": null}),
+    }
+}
+
+/// Parses CIDR notation, e.g. `1.0.0.0/24`, into an inclusive range.
+fn parse_cidr(cidr: &str) -> Option<RangeInclusive<IpAddr>> {
+    let (addr, prefix) = cidr.split_once('/')?;
+    let addr = IpAddr::from_str(addr).ok()?;
+    let prefix = u32::from_str(prefix).ok()?;
+    match addr {
+        IpAddr::V4(v4) => {
+            let host_mask = u32::MAX.checked_shr(prefix)?;
+            let start = u32::from(v4) & !host_mask;
+            Some(
+                IpAddr::from(start.to_be_bytes())..=IpAddr::from((start | host_mask).to_be_bytes()),
+            )
+        }
+        IpAddr::V6(v6) => {
+            let host_mask = u128::MAX.checked_shr(prefix)?;
+            let start = u128::from(v6) & !host_mask;
+            Some(
+                IpAddr::from(start.to_be_bytes())..=IpAddr::from((start | host_mask).to_be_bytes()),
+            )
+        }
+    }
+}
+
+fn bad_request(error: &str) -> (StatusCode, Json<Value>) {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error })))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_cidr, router};
+    use crate::{CountryCode, DbIpDatabase, SharedDatabase};
+    use ::axum::body::Body;
+    use http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn request(request: Request<Body>) -> (StatusCode, Value) {
+        let db =
+            DbIpDatabase::<CountryCode>::from_csv_str(include_str!("../test_country_data.csv"))
+                .unwrap();
+        let response = router(SharedDatabase::new(db))
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = ::axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn get(uri: &str) -> (StatusCode, Value) {
+        request(Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    #[test]
+    fn cidr() {
+        assert_eq!(
+            parse_cidr("1.2.3.4/16"),
+            Some("1.2.0.0".parse().unwrap()..="1.2.255.255".parse().unwrap())
+        );
+        assert!(parse_cidr("::/129").is_none());
+        assert!(parse_cidr("1.2.3.4").is_none());
+    }
+
+    #[tokio::test]
+    async fn lookup() {
+        assert_eq!(
+            get("/lookup/1.2.3.4").await,
+            (
+                StatusCode::OK,
+                json!({
+                    "ip": "1.2.3.4",
+                    "value": "AU",
+                    "start": "1.0.0.0",
+                    "end": "255.255.255.255",
+                })
+            )
+        );
+        assert_eq!(
+            get("/lookup/0.0.0.0").await,
+            (StatusCode::OK, json!({"ip": "0.0.0.0", "value": null}))
+        );
+        assert_eq!(get("/lookup/nope").await.0, StatusCode::BAD_REQUEST);
+
+        let (status, body) = request(
+            Request::post("/lookup")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"["1.2.3.4", "nope", "2a07:7ec5:8233::1"]"#))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["value"], "AU");
+        assert_eq!(body[1]["error"], "invalid address");
+        assert_eq!(body[2]["value"], "AU");
+    }
+
+    #[tokio::test]
+    async fn cidr_and_health() {
+        let (status, body) = get("/cidr/0.0.0.0/7").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["end"], "1.255.255.255");
+        assert_eq!(
+            body["ranges"],
+            json!([
+                {"start": "0.0.0.1", "end": "0.255.255.255", "value": "US"},
+                {"start": "1.0.0.0", "end": "255.255.255.255", "value": "AU"},
+            ])
+        );
+        assert_eq!(get("/cidr/0.0.0.0/33").await.0, StatusCode::BAD_REQUEST);
+
+        let (_, body) = get("/health").await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["ranges_v4"], 3);
+    }
+}
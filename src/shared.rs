@@ -0,0 +1,115 @@
+//! A database that can be replaced while it is being used, e.g. to reload it from disk.
+
+use crate::DbIpDatabase;
+#[cfg(all(feature = "csv", feature = "region"))]
+use crate::Region;
+#[cfg(feature = "csv")]
+use crate::{CountryCode, FromCsvError, IpData};
+use std::sync::{Arc, RwLock};
+
+/// A cheaply cloneable handle to a [`DbIpDatabase`] that may be replaced at any time.
+///
+/// Readers get an [`Arc`] to the current database, so a replacement doesn't affect lookups that
+/// are already in progress.
+pub struct SharedDatabase<V> {
+    current: Arc<RwLock<Arc<DbIpDatabase<V>>>>,
+}
+
+impl<V> SharedDatabase<V> {
+    pub fn new(db: DbIpDatabase<V>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(db))),
+        }
+    }
+
+    /// The current database.
+    pub fn load(&self) -> Arc<DbIpDatabase<V>> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Replaces the current database for all handles.
+    pub fn store(&self, db: DbIpDatabase<V>) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(db);
+    }
+
+    /// Replaces the current database with the result of `load`, unless it fails, in which case the
+    /// current database is kept.
+    pub fn reload<E>(&self, load: impl FnOnce() -> Result<DbIpDatabase<V>, E>) -> Result<(), E> {
+        self.store(load()?);
+        Ok(())
+    }
+}
+
+impl<V> Clone for SharedDatabase<V> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+/// Where to (re)load a [`SharedDatabase`] from.
+#[cfg(feature = "csv")]
+pub enum DatabaseSource<V> {
+    /// The database embedded at build time.
+    Embedded(fn() -> DbIpDatabase<V>),
+    /// A (possibly gzip-compressed, with the `gzip` feature) CSV file.
+    CsvFile(String),
+}
+
+#[cfg(feature = "csv")]
+impl<V: IpData> DatabaseSource<V> {
+    pub fn load(&self) -> Result<DbIpDatabase<V>, FromCsvError> {
+        match self {
+            Self::Embedded(embedded) => Ok(embedded()),
+            Self::CsvFile(path) => DbIpDatabase::from_csv_file(path),
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl DatabaseSource<CountryCode> {
+    /// The embedded country code database, if the `include-country-code-lite` feature is enabled.
+    pub fn embedded() -> Option<Self> {
+        #[cfg(feature = "include-country-code-lite")]
+        return Some(Self::Embedded(|| crate::include_country_code_database!()));
+        #[cfg(not(feature = "include-country-code-lite"))]
+        None
+    }
+}
+
+#[cfg(all(feature = "csv", feature = "region"))]
+impl DatabaseSource<Region> {
+    /// The embedded region database, if the `include-region-lite` feature is enabled.
+    pub fn embedded() -> Option<Self> {
+        #[cfg(feature = "include-region-lite")]
+        return Some(Self::Embedded(|| crate::include_region_database!()));
+        #[cfg(not(feature = "include-region-lite"))]
+        None
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "csv", feature = "ipv4", feature = "ipv6"))]
+mod test {
+    use super::SharedDatabase;
+    use crate::{CountryCode, DbIpDatabase};
+
+    #[test]
+    fn reload() {
+        let shared = SharedDatabase::<CountryCode>::new(
+            DbIpDatabase::from_csv_str("1.0.0.0,1.0.0.255,AU\n").unwrap(),
+        );
+        let clone = shared.clone();
+        let before = shared.load();
+
+        assert!(clone
+            .reload(|| DbIpDatabase::from_csv_str("1.0.0.0,1.0.0.255,US\n"))
+            .is_ok());
+        assert!(clone.reload(|| DbIpDatabase::from_csv_str("bad")).is_err());
+
+        let ip = "1.0.0.1".parse().unwrap();
+        assert_eq!(before.get(&ip), CountryCode::from_str("AU"));
+        assert_eq!(shared.load().get(&ip), CountryCode::from_str("US"));
+    }
+}